@@ -4,6 +4,7 @@ extern crate env_logger;
 extern crate chrono;
 
 extern crate explorer;
+extern crate mandelbrot_core;
 
 use std::io::Write;
 use log::Record;
@@ -19,6 +20,11 @@ fn main() {
 
     match Explorer::new(WINDOW_SIZE, UPDATE_FREQUENCY) {
         Ok(mut app) => {
+            if let Some((center, step_size)) = parse_location() {
+                if let Err(e) = app.set_location(&center, step_size) {
+                    error!("Invalid center: {}", e);
+                }
+            }
             app.run();        
         },
         Err(e) => {
@@ -27,6 +33,20 @@ fn main() {
     };
 }
 
+fn parse_location() -> Option<(String, f64)> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        return None;
+    }
+    match args[2].parse::<f64>() {
+        Ok(step_size) => Some((args[1].clone(), step_size)),
+        Err(e) => {
+            error!("Invalid step size: {}", e);
+            None
+        }
+    }
+}
+
 fn init_custom_logger() {
     let format = |buf: &mut Formatter , record: &Record| {
         let time = chrono::Local::now();
@@ -1,15 +1,26 @@
 use allegro;
-use allegro::{Color, Core, Display, EventQueue, Timer};
+use allegro::{Color, Core, Display, Event, EventQueue, Timer};
 use allegro_primitives::PrimitivesAddon;
 use chrono::Local;
 
-use crate::mandelbrot_core::Mandelbrot;
+use crate::mandelbrot_core::fixed::precision_for_step;
+use crate::mandelbrot_core::{
+    Buddhabrot, CancelToken, ColorMode, Coordinate, Fractal, InteriorMode, Lighting, Mandelbrot,
+    MandelbrotError, OrbitTrap, Overlay, RenderEvent, TileCache, Viewport,
+};
 use crate::ExplorerError;
 
+// the nebulabrot is traced on the event thread, sized to take a few seconds
+const NEBULABROT_SAMPLES: u64 = 2_000_000;
+const NEBULABROT_LIMITS: [u32; 3] = [2000, 200, 20];
+const PROGRESS_STEP: u32 = 25;
+
 pub struct Explorer {
     stop: bool,
     needs_update: bool,
     mandelbrot: Mandelbrot,
+    mandelbrot_viewport: Option<Viewport>,
+    tiles: TileCache,
     update_timer: Timer,
     shape: [i32; 2],
     event_queue: EventQueue,
@@ -95,6 +106,8 @@ impl Explorer {
             stop: false,
             needs_update: true,
             mandelbrot: mandelbrot,
+            mandelbrot_viewport: None,
+            tiles: TileCache::for_shape(screen_size),
             update_timer: update_timer,
             shape: screen_size,
             event_queue: event_queue,
@@ -105,6 +118,16 @@ impl Explorer {
         Ok(app)
     }
 
+    // the center is parsed with the precision of the step, a center rounded
+    // to the digits it was given with would be off by many pixels when zoomed in
+    pub fn set_location(&mut self, center: &str, step_size: f64) -> Result<(), MandelbrotError> {
+        let center = Coordinate::parse(center, precision_for_step(step_size))?;
+        self.mandelbrot.set_center(center);
+        self.mandelbrot.set_step_size(step_size);
+        self.needs_update = true;
+        Ok(())
+    }
+
     pub fn run(&mut self) {
         self.update_timer.start();
         while !self.stop {
@@ -115,15 +138,13 @@ impl Explorer {
                 allegro::MouseButtonDown { x, y, button, .. } => {
                     self.handle_mousedown([x, y], button);
                 }
-                allegro::TimerTick { .. } => {
-                    if self.needs_update {
-                        self.update();
-                        self.needs_update = false;
-                    }
+                allegro::TimerTick { .. } if self.needs_update => {
+                    self.needs_update = !self.update();
                 }
                 allegro::DisplayResize { width, height, .. } => {
                     info!("W = {}, H = {}", width, height);
                     self.shape = [width, height];
+                    self.tiles.set_shape(self.shape);
                     self.needs_update = true;
                 }
                 allegro::DisplayClose { .. } => {
@@ -167,6 +188,30 @@ impl Explorer {
                     Err(e) => error!("Zoomed sequence: {}", e),
                 }
             }
+            allegro::KeyCode::F6 => {
+                let name = format!("nebulabrot_{}", Local::now().format("%Y%m%d_%H%M%S"));
+                let nebulabrot = Buddhabrot::nebulabrot(NEBULABROT_SAMPLES, NEBULABROT_LIMITS);
+                info!("Starting nebulabrot snapshot...");
+                let mut reported = 0;
+                match self.mandelbrot.snapshot_buddhabrot(
+                    &nebulabrot,
+                    &name,
+                    [1920, 1080],
+                    &CancelToken::new(),
+                    |event| {
+                        if let RenderEvent::Progress(fraction) = event {
+                            let percent = (fraction * 100.) as u32;
+                            if percent >= reported + PROGRESS_STEP {
+                                reported = percent - percent % PROGRESS_STEP;
+                                info!("Nebulabrot progress: {}%", percent);
+                            }
+                        }
+                    },
+                ) {
+                    Ok(_) => info!("Finished nebulabrot snapshot!"),
+                    Err(e) => error!("Nebulabrot snapshot: {}", e),
+                }
+            }
             allegro::KeyCode::F3 => {
                 self.mandelbrot.randomize_start_color();
                 self.needs_update = true;
@@ -175,6 +220,81 @@ impl Explorer {
                 self.mandelbrot.set_step_default();
                 self.needs_update = true;
             }
+            allegro::KeyCode::J => {
+                self.toggle_julia();
+                self.needs_update = true;
+            }
+            allegro::KeyCode::C => {
+                let mode = match self.mandelbrot.get_color_mode() {
+                    ColorMode::Banded => ColorMode::Smooth,
+                    ColorMode::Smooth => ColorMode::Distance,
+                    ColorMode::Distance => ColorMode::Histogram,
+                    ColorMode::Histogram => ColorMode::OrbitTrap,
+                    ColorMode::OrbitTrap => ColorMode::StripeAverage,
+                    ColorMode::StripeAverage => ColorMode::TriangleAverage,
+                    ColorMode::TriangleAverage => ColorMode::Banded,
+                };
+                info!("color mode = {:?}", mode);
+                self.mandelbrot.set_color_mode(mode);
+                self.needs_update = true;
+            }
+            allegro::KeyCode::I => {
+                let mode = match self.mandelbrot.get_interior_mode() {
+                    InteriorMode::Black => InteriorMode::Magnitude,
+                    InteriorMode::Magnitude => InteriorMode::Period,
+                    InteriorMode::Period => InteriorMode::Distance,
+                    InteriorMode::Distance => InteriorMode::AtomDomain,
+                    InteriorMode::AtomDomain => InteriorMode::OrbitTrap,
+                    InteriorMode::OrbitTrap => InteriorMode::Black,
+                };
+                info!("interior mode = {:?}", mode);
+                self.mandelbrot.set_interior_mode(mode);
+                self.needs_update = true;
+            }
+            allegro::KeyCode::T => {
+                let center = [0., 0.];
+                let trap = match *self.mandelbrot.get_orbit_trap() {
+                    OrbitTrap::Point { .. } => OrbitTrap::Line {
+                        point: center,
+                        angle: 0.,
+                    },
+                    OrbitTrap::Line { .. } => OrbitTrap::Cross { center, angle: 0. },
+                    OrbitTrap::Cross { .. } => OrbitTrap::Circle {
+                        center,
+                        radius: 0.5,
+                    },
+                    _ => OrbitTrap::Point { center },
+                };
+                info!("orbit trap = {:?}", trap);
+                self.mandelbrot.set_orbit_trap(trap);
+                self.needs_update = true;
+            }
+            allegro::KeyCode::L => {
+                let lighting = match self.mandelbrot.get_lighting() {
+                    Some(_) => None,
+                    None => Some(Lighting::default()),
+                };
+                info!("lighting = {:?}", lighting);
+                self.mandelbrot.set_lighting(lighting);
+                self.needs_update = true;
+            }
+            allegro::KeyCode::O => {
+                let overlay = match self.mandelbrot.get_overlay() {
+                    None => Some(Overlay::BinaryDecomposition),
+                    Some(Overlay::BinaryDecomposition) => Some(Overlay::FieldLines),
+                    Some(Overlay::FieldLines) => None,
+                };
+                info!("overlay = {:?}", overlay);
+                self.mandelbrot.set_overlay(overlay);
+                self.needs_update = true;
+            }
+            allegro::KeyCode::F5 => {
+                info!(
+                    "location: {} {}",
+                    self.mandelbrot.get_center(),
+                    self.mandelbrot.get_step_size()
+                );
+            }
             allegro::KeyCode::Escape => {
                 self.stop = true;
             }
@@ -182,6 +302,24 @@ impl Explorer {
         }
     }
 
+    fn toggle_julia(&mut self) {
+        match self.mandelbrot.get_fractal() {
+            Fractal::Mandelbrot => {
+                let c = self.mandelbrot.get_center().to_f64();
+                self.mandelbrot_viewport = Some(self.mandelbrot.get_viewport().clone());
+                self.mandelbrot.set_fractal(Fractal::Julia(c));
+                self.mandelbrot.set_center(Coordinate::from_f64([0., 0.]));
+                self.mandelbrot.set_step_default();
+            }
+            Fractal::Julia(_) => {
+                if let Some(viewport) = self.mandelbrot_viewport.take() {
+                    self.mandelbrot.set_viewport(viewport);
+                }
+                self.mandelbrot.set_fractal(Fractal::Mandelbrot);
+            }
+        }
+    }
+
     fn handle_mousedown(&mut self, pos: [i32; 2], button: u32) {
         info!("pos = {}/{}, button = {}", pos[0], pos[1], button);
         match button {
@@ -194,28 +332,70 @@ impl Explorer {
         }
     }
 
-    fn update(&mut self) {
+    // returns false if the frame was abandoned because of new input
+    fn update(&mut self) -> bool {
         self.update_timer.stop();
         self.mandelbrot.print_stats();
-        let pixels = self.mandelbrot.create_pixel_triplets(self.shape);
-        self.render(&pixels);
+        let cancel = CancelToken::new();
+        let (core, event_queue, shape) = (&self.core, &self.event_queue, self.shape);
+        let result = self
+            .mandelbrot
+            .render_tiled(self.shape, &mut self.tiles, &cancel, |event| {
+                if let RenderEvent::Pass { pixels, .. } = event {
+                    render(core, shape, pixels);
+                }
+                if input_pending(event_queue) {
+                    cancel.cancel();
+                }
+            });
         self.update_timer.start();
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                info!("Update: {}", e);
+                false
+            }
+        }
     }
+}
 
-    fn render(&mut self, pixels: &[[u8; 3]]) {
-        self.core.clear_to_color(Color::from_rgb(0, 0, 0));
-        let mut iter = pixels.iter();
-        for y in 0..self.shape[1] {
-            for x in 0..self.shape[0] {
-                match iter.next() {
-                    Some(p) => {
-                        self.core
-                            .draw_pixel(x as f32, y as f32, Color::from_rgb(p[0], p[1], p[2]));
-                    }
-                    None => unreachable!(),
+fn render(core: &Core, shape: [i32; 2], pixels: &[[u8; 3]]) {
+    core.clear_to_color(Color::from_rgb(0, 0, 0));
+    let mut iter = pixels.iter();
+    for y in 0..shape[1] {
+        for x in 0..shape[0] {
+            match iter.next() {
+                Some(p) => {
+                    core.draw_pixel(x as f32, y as f32, Color::from_rgb(p[0], p[1], p[2]));
                 }
+                None => unreachable!(),
             }
         }
-        self.core.flip_display();
     }
+    core.flip_display();
+}
+
+// User input queued while rendering makes the current frame obsolete. Mouse
+// moves, key releases and the other events run ignores are dropped, so they
+// neither cancel the frame nor hide the input queued behind them.
+fn input_pending(event_queue: &EventQueue) -> bool {
+    loop {
+        match event_queue.peek_next_event() {
+            allegro::NoEvent => return false,
+            event if is_input(&event) => return true,
+            _ => {
+                event_queue.drop_next_event();
+            }
+        }
+    }
+}
+
+fn is_input(event: &Event) -> bool {
+    matches!(
+        event,
+        allegro::KeyDown { .. }
+            | allegro::MouseButtonDown { .. }
+            | allegro::DisplayResize { .. }
+            | allegro::DisplayClose { .. }
+    )
 }
@@ -4,6 +4,7 @@ extern crate chrono;
 extern crate env_logger;
 
 extern crate generator;
+extern crate mandelbrot_core;
 
 use env_logger::fmt::Formatter;
 use env_logger::Builder;
@@ -11,19 +12,38 @@ use log::Record;
 use std::io::Write;
 
 use generator::Generator;
+use mandelbrot_core::Supersampling;
 
 fn main() {
     const SNAPSHOT_SIZE: [i32; 2] = [1920, 1080];
     const ENTROPY_THRESHOLD: f32 = 4.;
+    const JULIA_RATIO: f64 = 0.25;
+    // 3x3 samples for the pixels on the filaments, where neighbours differ
+    // by more than an iteration
+    const SUPERSAMPLING: Supersampling = Supersampling::Adaptive {
+        samples: 3,
+        threshold: 1.,
+    };
     init_custom_logger();
 
     match Generator::new(SNAPSHOT_SIZE, ENTROPY_THRESHOLD) {
-        Ok(mut generator) => match generator.run() {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Generator::run: {}", e);
+        Ok(mut generator) => {
+            generator.set_julia_ratio(JULIA_RATIO);
+            generator.set_supersampling(SUPERSAMPLING);
+            // an optional image whose colors theme the snapshots
+            if let Some(path) = std::env::args().nth(1) {
+                if let Err(e) = generator.set_theme_image(&path) {
+                    error!("Generator::set_theme_image: {}", e);
+                    return;
+                }
+            }
+            match generator.run() {
+                Ok(_) => {}
+                Err(e) => {
+                    error!("Generator::run: {}", e);
+                }
             }
-        },
+        }
         Err(e) => {
             error!("Generator::new: {}", e);
         }
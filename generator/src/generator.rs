@@ -3,12 +3,24 @@ use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
 use crate::GeneratorError;
-use mandelbrot_core::Mandelbrot;
+use mandelbrot_core::{
+    extract_gradient, CancelToken, Coordinate, Fractal, Gradient, Mandelbrot, MandelbrotError,
+    RenderEvent, Supersampling,
+};
+
+const PROGRESS_STEP: u32 = 25;
+const THEME_COLORS: usize = 8;
+// snapshots are rendered and written a band of rows at a time, nobody watches
+// them being drawn so there are no coarse passes
+const BAND_HEIGHT: i32 = 64;
 
 pub struct Generator {
     snapshot_size: [i32; 2],
     entropy_threshold: f32,
+    julia_ratio: f64,
+    theme: Option<Gradient>,
     mandelbrot: Mandelbrot,
+    cancel: CancelToken,
     rng: StdRng,
 }
 
@@ -22,17 +34,45 @@ impl Generator {
         let generator = Self {
             snapshot_size: snapshot_size,
             entropy_threshold: entropy_threshold,
+            julia_ratio: 0.,
+            theme: None,
             mandelbrot: mandelbrot,
+            cancel: CancelToken::new(),
             rng: StdRng::from_entropy(),
         };
 
         Ok(generator)
     }
 
+    // share of julia snapshots, gen_bool only takes probabilities in [0, 1]
+    pub fn set_julia_ratio(&mut self, ratio: f64) {
+        self.julia_ratio = if ratio.is_nan() {
+            0.
+        } else {
+            ratio.clamp(0., 1.)
+        };
+    }
+
+    pub fn set_supersampling(&mut self, supersampling: Supersampling) {
+        self.mandelbrot.set_supersampling(supersampling);
+    }
+
+    // colors of the snapshots are taken from the image instead of random hues
+    pub fn set_theme_image(&mut self, path: &str) -> Result<(), GeneratorError> {
+        self.theme = Some(extract_gradient(path, THEME_COLORS)?);
+        Ok(())
+    }
+
+    // cancelling the token aborts the running snapshot and stops the generator
+    pub fn get_cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
     pub fn run(&mut self) -> Result<(), GeneratorError> {
-        loop {
+        while !self.cancel.is_cancelled() {
             self.cycle()?;
         }
+        Ok(())
     }
 
     fn cycle(&mut self) -> Result<(), GeneratorError> {
@@ -43,36 +83,78 @@ impl Generator {
                 self.snapshot_size[0], self.snapshot_size[1]
             );
             let name = format!("{}", Local::now().format("%Y%m%d_%H%M%S"));
-            let center = self.mandelbrot.get_center();
             info!(
-                "center = {}/{}, step_size = {}, depth = {}, entropy = {}, file = '{}'",
-                center[0],
-                center[1],
+                "fractal = {:?}, center = {}, step_size = {}, depth = {}, entropy = {}, file = '{}'",
+                self.mandelbrot.get_fractal(),
+                self.mandelbrot.get_center(),
                 self.mandelbrot.get_step_size(),
                 self.mandelbrot.get_depth(),
                 entropy,
                 name
             );
-            self.mandelbrot.snapshot(&name, self.snapshot_size)?;
-            info!("Snapshot finished!");
+            let mut reported = 0;
+            let result = self.mandelbrot.snapshot_streamed(
+                &name,
+                self.snapshot_size,
+                BAND_HEIGHT,
+                &self.cancel,
+                |event| {
+                    if let RenderEvent::Progress(fraction) = event {
+                        let percent = (fraction * 100.) as u32;
+                        if percent >= reported + PROGRESS_STEP {
+                            reported = percent - percent % PROGRESS_STEP;
+                            info!("Snapshot progress: {}%", percent);
+                        }
+                    }
+                },
+            );
+            match result {
+                Ok(_) => info!("Snapshot finished!"),
+                Err(MandelbrotError::Cancelled) => info!("Snapshot cancelled"),
+                Err(e) => return Err(e.into()),
+            }
         }
         Ok(())
     }
 
     fn randomize_mandelbrot(&mut self) -> f32 {
+        let fractal = self.get_random_fractal();
         let pos = self.get_random_pos();
         let step_size = self.get_random_step_size();
         let bucket_count = self.get_random_bucket_count();
+        self.mandelbrot.set_fractal(fractal);
         self.mandelbrot.set_center(pos);
         self.mandelbrot.set_step_size(step_size);
-        self.mandelbrot
-            .randomize_continuos_color_ranged(bucket_count as usize);
+        match self.theme {
+            Some(ref theme) => {
+                let mut gradient = theme.clone();
+                gradient.set_cycle_length(bucket_count as f64);
+                gradient.set_offset(self.rng.gen_range(0., 1.));
+                self.mandelbrot.set_gradient(gradient);
+            }
+            None => self
+                .mandelbrot
+                .randomize_continuos_color_ranged(bucket_count as usize),
+        }
         let entropy = self.mandelbrot.estimate_entropy(self.snapshot_size);
         entropy
     }
 
-    fn get_random_pos(&mut self) -> [f64; 2] {
-        [self.rng.gen_range(-2., 2.), self.rng.gen_range(-2., 2.)]
+    fn get_random_fractal(&mut self) -> Fractal {
+        if self.rng.gen_bool(self.julia_ratio) {
+            // parameters close to the main cardioid give connected, detailed julia sets
+            let angle: f64 = self.rng.gen_range(0., 2. * std::f64::consts::PI);
+            let radius: f64 = self.rng.gen_range(0.95, 1.05);
+            Fractal::Julia([
+                radius * (angle.cos() / 2. - (2. * angle).cos() / 4.),
+                radius * (angle.sin() / 2. - (2. * angle).sin() / 4.),
+            ])
+        } else {
+            Fractal::Mandelbrot
+        }
+    }
+    fn get_random_pos(&mut self) -> Coordinate {
+        Coordinate::from_f64([self.rng.gen_range(-2., 2.), self.rng.gen_range(-2., 2.)])
     }
     fn get_random_step_size(&mut self) -> f64 {
         self.rng.gen_range(1e-14, 1e-4)
@@ -0,0 +1,272 @@
+use num::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::kernel::in_main_components;
+use crate::{CancelToken, Formula, MandelbrotError, RenderEvent, Viewport};
+
+// the whole set lies in this square around the origin, c is sampled from it
+const SAMPLE_RADIUS: f64 = 2.;
+// |z| > 2 escapes for z^2 + c, larger radii only add points far from the set
+const BAILOUT: f64 = 4.;
+// orbits escaping sooner are left out, the points of the many short orbits
+// outside the set would cover the image in a uniform haze
+const DEFAULT_MIN_ITERATIONS: u32 = 10;
+// samples drawn from one generator, the unit of work of a thread
+const CHUNK_SIZE: u64 = 1 << 14;
+// chunks traced between two progress reports
+const CHUNK_BATCH: usize = 32;
+
+// The orbits drawn: those escaping within the limit (Buddhabrot), or those
+// still bounded at the limit (anti-Buddhabrot).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orbits {
+    Escaping,
+    Bounded,
+}
+
+// Density of the orbits of random c values, counted per pixel of the
+// viewport. Every color channel has its own iteration limit; equal limits
+// give the gray Buddhabrot, different ones the Nebulabrot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Buddhabrot {
+    samples: u64,
+    limits: [u32; 3],
+    min_iterations: u32,
+    orbits: Orbits,
+    seed: u64,
+}
+
+impl Buddhabrot {
+    pub fn new(samples: u64, limit: u32) -> Buddhabrot {
+        Self::nebulabrot(samples, [limit; 3])
+    }
+
+    pub fn nebulabrot(samples: u64, limits: [u32; 3]) -> Buddhabrot {
+        Self {
+            samples,
+            limits,
+            min_iterations: DEFAULT_MIN_ITERATIONS,
+            orbits: Orbits::Escaping,
+            seed: 0,
+        }
+    }
+
+    pub fn set_samples(&mut self, samples: u64) {
+        self.samples = samples;
+    }
+
+    pub fn set_limits(&mut self, limits: [u32; 3]) {
+        self.limits = limits;
+    }
+
+    pub fn set_min_iterations(&mut self, min_iterations: u32) {
+        self.min_iterations = min_iterations;
+    }
+
+    pub fn set_orbits(&mut self, orbits: Orbits) {
+        self.orbits = orbits;
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn get_samples(&self) -> u64 {
+        self.samples
+    }
+    pub fn get_limits(&self) -> [u32; 3] {
+        self.limits
+    }
+    pub fn get_min_iterations(&self) -> u32 {
+        self.min_iterations
+    }
+    pub fn get_orbits(&self) -> Orbits {
+        self.orbits
+    }
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Hits per pixel and channel, row by row. Every chunk of samples has its
+    // own generator seeded from the seed, so the counts do not depend on how
+    // the chunks are spread over the threads.
+    pub fn accumulate(
+        &self,
+        viewport: &Viewport,
+        formula: &dyn Formula,
+        shape: [i32; 2],
+    ) -> Vec<[u32; 3]> {
+        self.trace_chunks(&self.chunks(), viewport, formula, shape)
+    }
+
+    // Like accumulate, a batch of chunks at a time, with the cancel token
+    // checked and the progress reported in between.
+    pub fn accumulate_cancellable<F: FnMut(RenderEvent)>(
+        &self,
+        viewport: &Viewport,
+        formula: &dyn Formula,
+        shape: [i32; 2],
+        cancel: &CancelToken,
+        mut on_event: F,
+    ) -> Result<Vec<[u32; 3]>, MandelbrotError> {
+        let chunks = self.chunks();
+        let mut hits = vec![[0; 3]; (shape[0] * shape[1]) as usize];
+        let mut done = 0;
+        for batch in chunks.chunks(CHUNK_BATCH) {
+            if cancel.is_cancelled() {
+                return Err(MandelbrotError::Cancelled);
+            }
+            add_hits(
+                &mut hits,
+                &self.trace_chunks(batch, viewport, formula, shape),
+            );
+            done += batch.len();
+            on_event(RenderEvent::Progress(done as f64 / chunks.len() as f64));
+        }
+        Ok(hits)
+    }
+
+    // RGB pixels, every channel scaled to its own maximum with a square root
+    // to bring out the faint orbits
+    pub fn render(&self, viewport: &Viewport, formula: &dyn Formula, shape: [i32; 2]) -> Vec<u8> {
+        to_pixels(&self.accumulate(viewport, formula, shape))
+    }
+
+    pub fn render_cancellable<F: FnMut(RenderEvent)>(
+        &self,
+        viewport: &Viewport,
+        formula: &dyn Formula,
+        shape: [i32; 2],
+        cancel: &CancelToken,
+        on_event: F,
+    ) -> Result<Vec<u8>, MandelbrotError> {
+        let hits = self.accumulate_cancellable(viewport, formula, shape, cancel, on_event)?;
+        Ok(to_pixels(&hits))
+    }
+
+    // the seed and sample count of every chunk
+    fn chunks(&self) -> Vec<(u64, u64)> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        (0..self.samples)
+            .step_by(CHUNK_SIZE as usize)
+            .map(|start| (rng.gen(), CHUNK_SIZE.min(self.samples - start)))
+            .collect()
+    }
+
+    fn trace_chunks(
+        &self,
+        chunks: &[(u64, u64)],
+        viewport: &Viewport,
+        formula: &dyn Formula,
+        shape: [i32; 2],
+    ) -> Vec<[u32; 3]> {
+        let size = (shape[0] * shape[1]) as usize;
+        chunks
+            .par_iter()
+            .fold(
+                || vec![[0; 3]; size],
+                |mut hits, &(seed, count)| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let mut orbit = Vec::new();
+                    for _ in 0..count {
+                        let c = Complex::new(
+                            rng.gen_range(-SAMPLE_RADIUS, SAMPLE_RADIUS),
+                            rng.gen_range(-SAMPLE_RADIUS, SAMPLE_RADIUS),
+                        );
+                        self.trace(formula, c, &mut orbit, viewport, shape, &mut hits);
+                    }
+                    hits
+                },
+            )
+            .reduce(
+                || vec![[0; 3]; size],
+                |mut a, b| {
+                    add_hits(&mut a, &b);
+                    a
+                },
+            )
+    }
+
+    // iterates c up to the largest limit and adds its orbit to the channels
+    // whose limit it is drawn for
+    fn trace(
+        &self,
+        formula: &dyn Formula,
+        c: Complex<f64>,
+        orbit: &mut Vec<Complex<f64>>,
+        viewport: &Viewport,
+        shape: [i32; 2],
+        hits: &mut [[u32; 3]],
+    ) {
+        let max_limit = *self.limits.iter().max().unwrap();
+        // the main components never escape
+        if self.orbits == Orbits::Escaping && formula.is_quadratic() && in_main_components(c) {
+            return;
+        }
+        orbit.clear();
+        let mut escape = None;
+        let (mut z, mut previous) = (Complex::new(0., 0.), Complex::new(0., 0.));
+        for i in 0..max_limit {
+            let next = formula.iterate(z, previous, c);
+            previous = z;
+            z = next;
+            if z.norm_sqr() >= BAILOUT {
+                escape = Some(i);
+                break;
+            }
+            orbit.push(z);
+        }
+
+        let center = viewport.get_center().to_f64();
+        let step = viewport.get_step_size();
+        for (channel, limit) in self.limits.iter().enumerate() {
+            let drawn = match (self.orbits, escape) {
+                (Orbits::Escaping, Some(i)) => i < *limit && i >= self.min_iterations,
+                (Orbits::Escaping, None) => false,
+                (Orbits::Bounded, Some(i)) => i >= *limit,
+                (Orbits::Bounded, None) => true,
+            };
+            if !drawn {
+                continue;
+            }
+            for z in orbit.iter().take(*limit as usize) {
+                let x = ((z.re - center[0]) / step).round() as i64 + (shape[0] / 2) as i64;
+                let y = ((z.im - center[1]) / step).round() as i64 + (shape[1] / 2) as i64;
+                if x >= 0 && y >= 0 && x < shape[0] as i64 && y < shape[1] as i64 {
+                    hits[(y * shape[0] as i64 + x) as usize][channel] += 1;
+                }
+            }
+        }
+    }
+}
+
+fn add_hits(hits: &mut [[u32; 3]], other: &[[u32; 3]]) {
+    for (a, b) in hits.iter_mut().zip(other.iter()) {
+        for (a, b) in a.iter_mut().zip(b.iter()) {
+            *a += b;
+        }
+    }
+}
+
+fn to_pixels(hits: &[[u32; 3]]) -> Vec<u8> {
+    let mut max = [0; 3];
+    for pixel in hits.iter() {
+        for (m, h) in max.iter_mut().zip(pixel.iter()) {
+            *m = (*m).max(*h);
+        }
+    }
+    let mut pixels = Vec::with_capacity(hits.len() * 3);
+    for pixel in hits.iter() {
+        for (h, m) in pixel.iter().zip(max.iter()) {
+            let value = if *m > 0 {
+                (*h as f64 / *m as f64).sqrt()
+            } else {
+                0.
+            };
+            pixels.push((value * 255.).round() as u8);
+        }
+    }
+    pixels
+}
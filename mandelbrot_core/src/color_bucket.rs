@@ -1,7 +1,4 @@
 use palette::{Hsv, Pixel, Srgb};
-use rand::rngs::ThreadRng;
-use rand::thread_rng;
-use rand::Rng;
 
 #[derive(Clone, Copy)]
 pub struct ColorBucket {
@@ -9,6 +6,10 @@ pub struct ColorBucket {
 }
 
 impl ColorBucket {
+    pub fn new(color: [u8; 3]) -> ColorBucket {
+        Self { color }
+    }
+
     pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> ColorBucket {
         let rgb_f: [f32; 3] = Srgb::from(Hsv::new(360. * hue, saturation, value)).into_raw();
         Self {
@@ -20,26 +21,12 @@ impl ColorBucket {
         }
     }
 
-    pub fn get_hue(&self) -> f32 {
-        Hsv::from(Srgb::new(
-            self.color[0] as f32 / 255.,
-            self.color[1] as f32 / 255.,
-            self.color[2] as f32 / 255.,
-        ))
-        .into_components()
-        .0
-        .to_positive_degrees()
-            / 360.
-    }
-
-    pub fn random_bucket() -> ColorBucket {
-        let hue = thread_rng().gen_range(0., 1.);
-        ColorBucket::from_hsv(hue, 1., 1.)
-    }
-
-    pub fn next_bucket(&self, loop_depth: i32) -> ColorBucket {
-        let hue = self.get_hue() + (1. / loop_depth as f32);
-        ColorBucket::from_hsv(hue, 1., 1.)
+    pub fn shade(&self, factor: f32) -> ColorBucket {
+        let mut color = self.color;
+        for c in color.iter_mut() {
+            *c = (*c as f32 * factor) as u8;
+        }
+        Self { color }
     }
 
     pub fn get_color(&self) -> [u8; 3] {
@@ -0,0 +1,300 @@
+use histogram::Histogram;
+use num::Complex;
+
+use crate::{ColorBucket, Gradient, Sample};
+
+// distance to the set in pixels, below which the palette color is darkened
+const DISTANCE_SCALE: f64 = 2.;
+const MAGNITUDE_SCALE: f64 = 50.;
+const INTERIOR_DISTANCE_SCALE: f64 = 10.;
+const TRAP_SCALE: f64 = 20.;
+// half width of the field lines in half turns of the escaping z, and the
+// brightness of the lower half of the binary decomposition
+const FIELD_LINE_WIDTH: f64 = 0.06;
+const DECOMPOSITION_SHADE: f32 = 0.5;
+// golden ratio conjugate, spreads consecutive indices over the hue circle
+const HUE_STEP: f32 = 0.618_034;
+// significant figures of the iteration histogram and its bins per iteration,
+// fine enough for views where every pixel escapes within a few iterations
+const HISTOGRAM_PRECISION: u32 = 4;
+const HISTOGRAM_RESOLUTION: f64 = 256.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    Banded,
+    Smooth,
+    Distance,
+    Histogram,
+    OrbitTrap,
+    StripeAverage,
+    TriangleAverage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InteriorMode {
+    Black,
+    Magnitude,
+    Period,
+    Distance,
+    AtomDomain,
+    OrbitTrap,
+}
+
+// Light source for the relief shading; angle in degrees around the image
+// plane, height above it relative to the surface normal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lighting {
+    pub angle: f64,
+    pub height: f64,
+    pub strength: f64,
+}
+
+// Drawn over the palette color of escaping points. The binary decomposition
+// darkens those whose last z has a negative imaginary part; the boundaries of
+// these cells, where z escapes along the real axis, are the field lines.
+// Their angles double with each iteration band, so they follow the external
+// angles k / 2^n down to the set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overlay {
+    BinaryDecomposition,
+    FieldLines,
+}
+
+// Parameters of the average coloring modes: stripes per turn around the
+// origin, and passes through the gradient over the range of the average
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Averaging {
+    pub density: f64,
+    pub frequency: f64,
+}
+
+// Cumulative distribution of the escape iterations of a frame, maps an
+// iteration count to its share of escaped pixels escaping earlier
+pub struct Equalization {
+    spans: Vec<Span>,
+    total: f64,
+}
+
+// iterations start..start + width with count samples, before escaping earlier
+struct Span {
+    start: f64,
+    width: f64,
+    before: f64,
+    count: f64,
+}
+
+pub struct Colorizer<'a> {
+    mode: ColorMode,
+    interior_mode: InteriorMode,
+    gradient: &'a Gradient,
+    step_size: f64,
+    lighting: Option<Lighting>,
+    averaging: Averaging,
+    overlay: Option<Overlay>,
+    equalization: Option<Equalization>,
+}
+
+impl Lighting {
+    pub fn new(angle: f64, height: f64, strength: f64) -> Lighting {
+        Self {
+            angle,
+            height,
+            strength,
+        }
+    }
+
+    pub fn shade(&self, normal: Complex<f64>) -> f64 {
+        let light = Complex::from_polar(&1., &self.angle.to_radians());
+        let brightness =
+            (normal.re * light.re + normal.im * light.im + self.height) / (1. + self.height);
+        1. - self.strength + self.strength * brightness.max(0.)
+    }
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self::new(45., 1.5, 0.75)
+    }
+}
+
+impl Overlay {
+    pub fn shade(&self, angle: f64) -> f32 {
+        match *self {
+            Overlay::BinaryDecomposition if angle >= 0.5 => DECOMPOSITION_SHADE,
+            Overlay::BinaryDecomposition => 1.,
+            Overlay::FieldLines => {
+                let half_turns = (angle * 2.).fract();
+                let distance = half_turns.min(1. - half_turns);
+                (distance / FIELD_LINE_WIDTH).min(1.) as f32
+            }
+        }
+    }
+}
+
+impl Averaging {
+    pub fn new(density: f64, frequency: f64) -> Averaging {
+        Self { density, frequency }
+    }
+}
+
+impl Default for Averaging {
+    fn default() -> Self {
+        Self::new(5., 1.)
+    }
+}
+
+impl Equalization {
+    pub fn new(samples: &[Sample], depth: u32) -> Equalization {
+        Self::from_iterations(samples.iter().filter_map(|s| s.iterations), depth)
+    }
+
+    // the iteration counts of the escaping pixels of the frame
+    pub fn from_iterations<I: Iterator<Item = f64>>(iterations: I, depth: u32) -> Equalization {
+        let mut histogram = Histogram::configure()
+            .precision(HISTOGRAM_PRECISION)
+            .max_value(((depth + 1) as f64 * HISTOGRAM_RESOLUTION) as u64)
+            .build()
+            .unwrap();
+        for v in iterations {
+            histogram
+                .increment((v * HISTOGRAM_RESOLUTION) as u64)
+                .unwrap();
+        }
+        let mut spans = Vec::new();
+        let mut before = 0.;
+        for bucket in histogram.into_iter().filter(|b| b.count() > 0) {
+            // a bucket holds the values up to and including its value
+            let width = bucket.width() as f64;
+            spans.push(Span {
+                start: (bucket.value() as f64 + 1. - width) / HISTOGRAM_RESOLUTION,
+                width: width / HISTOGRAM_RESOLUTION,
+                before,
+                count: bucket.count() as f64,
+            });
+            before += bucket.count() as f64;
+        }
+        Self {
+            spans,
+            total: before,
+        }
+    }
+
+    // position of the iteration count in the distribution, between 0 and 1
+    pub fn position(&self, v: f64) -> f64 {
+        match self.spans.partition_point(|s| s.start <= v) {
+            0 => 0.,
+            i => {
+                let span = &self.spans[i - 1];
+                let fraction = ((v - span.start) / span.width).min(1.);
+                (span.before + span.count * fraction) / self.total
+            }
+        }
+    }
+}
+
+impl<'a> Colorizer<'a> {
+    pub fn new(
+        mode: ColorMode,
+        interior_mode: InteriorMode,
+        gradient: &'a Gradient,
+        step_size: f64,
+        lighting: Option<Lighting>,
+        averaging: Averaging,
+        overlay: Option<Overlay>,
+    ) -> Colorizer<'a> {
+        Self {
+            mode,
+            interior_mode,
+            gradient,
+            step_size,
+            lighting,
+            averaging,
+            overlay,
+            equalization: None,
+        }
+    }
+
+    pub fn set_equalization(&mut self, equalization: Equalization) {
+        self.equalization = Some(equalization);
+    }
+
+    pub fn colorize(&self, sample: &Sample) -> [u8; 3] {
+        let v = match sample.iterations {
+            Some(v) => v,
+            None => {
+                return match self.interior(sample) {
+                    Some(color) => color.get_color(),
+                    None => [0, 0, 0],
+                }
+            }
+        };
+        let color = match self.mode {
+            ColorMode::Banded => self.banded(v),
+            ColorMode::Smooth => self.smooth(v),
+            // the distribution is spread over one cycle of the gradient
+            ColorMode::Histogram => match self.equalization {
+                Some(ref equalization) => {
+                    self.smooth(equalization.position(v) * self.gradient.get_cycle_length())
+                }
+                None => self.smooth(v),
+            },
+            ColorMode::Distance => match sample.distance {
+                Some(d) => {
+                    let shade = (d / self.step_size / DISTANCE_SCALE).min(1.).sqrt();
+                    self.smooth(v).shade(shade as f32)
+                }
+                None => self.smooth(v),
+            },
+            // orbits missing the image trap keep their escape color
+            ColorMode::OrbitTrap => self.trap(sample).unwrap_or_else(|| self.smooth(v)),
+            ColorMode::StripeAverage | ColorMode::TriangleAverage => match sample.average {
+                Some(a) => {
+                    self.smooth(a * self.averaging.frequency * self.gradient.get_cycle_length())
+                }
+                None => self.smooth(v),
+            },
+        };
+        let color = match (self.lighting, sample.normal) {
+            (Some(lighting), Some(normal)) => color.shade(lighting.shade(normal) as f32),
+            _ => color,
+        };
+        match (self.overlay, sample.angle) {
+            (Some(overlay), Some(angle)) => color.shade(overlay.shade(angle)),
+            _ => color,
+        }
+        .get_color()
+    }
+
+    fn interior(&self, sample: &Sample) -> Option<ColorBucket> {
+        match self.interior_mode {
+            InteriorMode::Black => None,
+            InteriorMode::Magnitude => sample.magnitude.map(|m| self.smooth(m * MAGNITUDE_SCALE)),
+            InteriorMode::Period => sample.period.map(indexed),
+            InteriorMode::Distance => sample
+                .interior_distance
+                .map(|d| self.smooth((d / self.step_size).max(1.).ln() * INTERIOR_DISTANCE_SCALE)),
+            InteriorMode::AtomDomain => sample.atom_domain.map(indexed),
+            InteriorMode::OrbitTrap => self.trap(sample),
+        }
+    }
+
+    fn trap(&self, sample: &Sample) -> Option<ColorBucket> {
+        match (sample.trap_color, sample.trap_distance) {
+            (Some(color), _) => Some(ColorBucket::new(color)),
+            (None, Some(d)) => Some(self.smooth(-d.max(f64::MIN_POSITIVE).ln() * TRAP_SCALE)),
+            _ => None,
+        }
+    }
+
+    fn banded(&self, v: f64) -> ColorBucket {
+        self.gradient.color(v.floor())
+    }
+
+    fn smooth(&self, v: f64) -> ColorBucket {
+        self.gradient.color(v)
+    }
+}
+
+fn indexed(index: u32) -> ColorBucket {
+    ColorBucket::from_hsv((index as f32 * HUE_STEP).fract(), 0.7, 0.8)
+}
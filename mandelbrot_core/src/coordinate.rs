@@ -0,0 +1,95 @@
+use std::cmp::max;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::fixed::precision_for_step;
+use crate::{Fixed, MandelbrotError};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coordinate {
+    re: Fixed,
+    im: Fixed,
+}
+
+impl Coordinate {
+    pub fn new(re: Fixed, im: Fixed) -> Coordinate {
+        Self { re, im }
+    }
+
+    pub fn from_f64(pos: [f64; 2]) -> Coordinate {
+        // goes through the shortest decimal representation, so the
+        // coordinate prints the same digits the f64 would
+        match format!("{},{}", pos[0], pos[1]).parse() {
+            Ok(c) => c,
+            Err(_) => Coordinate::new(Fixed::from_f64(pos[0], 64), Fixed::from_f64(pos[1], 64)),
+        }
+    }
+
+    // "<re>,<im>" with at least the given precision, see Fixed::parse
+    pub fn parse(s: &str, bits: u32) -> Result<Coordinate, MandelbrotError> {
+        let mut parts = s.split(',');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(re), Some(im), None) => Ok(Coordinate::new(
+                Fixed::parse(re, bits)?,
+                Fixed::parse(im, bits)?,
+            )),
+            _ => Err(MandelbrotError::Parse(format!(
+                "invalid coordinate '{}', expected '<re>,<im>'",
+                s
+            ))),
+        }
+    }
+
+    pub fn to_f64(&self) -> [f64; 2] {
+        [self.re.to_f64(), self.im.to_f64()]
+    }
+
+    pub fn get_re(&self) -> &Fixed {
+        &self.re
+    }
+
+    pub fn get_im(&self) -> &Fixed {
+        &self.im
+    }
+
+    pub fn get_bits(&self) -> u32 {
+        max(self.re.get_bits(), self.im.get_bits())
+    }
+
+    pub fn with_bits(&self, bits: u32) -> Coordinate {
+        Self {
+            re: self.re.with_bits(bits),
+            im: self.im.with_bits(bits),
+        }
+    }
+
+    pub fn add(&self, other: &Coordinate) -> Coordinate {
+        Self {
+            re: &self.re + &other.re,
+            im: &self.im + &other.im,
+        }
+    }
+
+    pub fn offset(&self, units: [i32; 2], step_size: f64) -> Coordinate {
+        let bits = max(self.get_bits(), precision_for_step(step_size));
+        let delta = Coordinate::new(
+            Fixed::from_f64(step_size * units[0] as f64, bits),
+            Fixed::from_f64(step_size * units[1] as f64, bits),
+        );
+        self.with_bits(bits).add(&delta)
+    }
+}
+
+impl FromStr for Coordinate {
+    type Err = MandelbrotError;
+
+    fn from_str(s: &str) -> Result<Coordinate, MandelbrotError> {
+        Coordinate::parse(s, 0)
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.re, self.im)
+    }
+}
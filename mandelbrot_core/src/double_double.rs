@@ -0,0 +1,135 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use num::Complex;
+
+use crate::kernel::BAILOUT;
+use crate::sample::{Sample, Tracker};
+use crate::Fixed;
+
+const SPLITTER: f64 = 134_217_729.;
+
+// Unevaluated sum hi + lo of two f64, giving roughly 106 bits of mantissa
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl DoubleDouble {
+    pub fn from_f64(value: f64) -> DoubleDouble {
+        Self { hi: value, lo: 0. }
+    }
+
+    pub fn from_fixed(value: &Fixed) -> DoubleDouble {
+        let hi = value.to_f64();
+        let lo = (value - &Fixed::from_f64(hi, value.get_bits())).to_f64();
+        Self::from_parts(hi, lo)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn square(self) -> DoubleDouble {
+        self * self
+    }
+
+    pub fn double(self) -> DoubleDouble {
+        Self {
+            hi: self.hi * 2.,
+            lo: self.lo * 2.,
+        }
+    }
+
+    fn from_parts(hi: f64, lo: f64) -> DoubleDouble {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        Self { hi, lo }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (sum, err) = two_sum(self.hi, other.hi);
+        DoubleDouble::from_parts(sum, err + self.lo + other.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let (product, err) = two_prod(self.hi, other.hi);
+        DoubleDouble::from_parts(product, err + self.hi * other.lo + self.lo * other.hi)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+pub fn check_double_double(
+    start: &[DoubleDouble; 2],
+    param: &[DoubleDouble; 2],
+    max_depth: u32,
+    mut tracker: Tracker,
+) -> Sample {
+    let (mut z_re, mut z_im) = (start[0], start[1]);
+    let c = Complex::new(param[0].to_f64(), param[1].to_f64());
+    for i in 0..max_depth {
+        tracker.advance(Complex::new(z_re.to_f64(), z_im.to_f64()), c);
+        let next_im = (z_re * z_im).double() + param[1];
+        z_re = z_re.square() - z_im.square() + param[0];
+        z_im = next_im;
+        if z_re.hi * z_re.hi + z_im.hi * z_im.hi >= BAILOUT {
+            return tracker.escaped(i, Complex::new(z_re.hi, z_im.hi));
+        }
+    }
+    tracker.interior(
+        Complex::new(z_re.to_f64(), z_im.to_f64()),
+        Complex::new(0., 0.),
+        c,
+    )
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let a_virtual = sum - b_virtual;
+    (sum, (a - a_virtual) + (b - b_virtual))
+}
+
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    (sum, b - (sum - a))
+}
+
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+    let err = ((a_hi * b_hi - product) + a_hi * b_lo + a_lo * b_hi) + a_lo * b_lo;
+    (product, err)
+}
+
+fn split(a: f64) -> (f64, f64) {
+    let t = SPLITTER * a;
+    let hi = t - (t - a);
+    (hi, a - hi)
+}
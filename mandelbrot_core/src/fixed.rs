@@ -0,0 +1,273 @@
+use std::cmp::max;
+use std::f64::consts::LOG2_10;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use num::{BigInt, Signed, ToPrimitive, Zero};
+
+use crate::MandelbrotError;
+
+// the mantissa is cut to this many bits, which convert exactly through a u64,
+// before it becomes an f64
+const CONVERSION_BITS: usize = 64;
+const DEFAULT_BITS: u32 = 64;
+const PRECISION_MARGIN: u32 = 64;
+// decimal exponents beyond this are rejected, 10^exponent is computed exactly
+const MAX_EXPONENT: i32 = 100_000;
+
+// Binary fixed point number: value = mantissa / 2^bits
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixed {
+    mantissa: BigInt,
+    bits: u32,
+}
+
+impl Fixed {
+    pub fn zero(bits: u32) -> Fixed {
+        Self {
+            mantissa: BigInt::zero(),
+            bits,
+        }
+    }
+
+    pub fn from_f64(value: f64, bits: u32) -> Fixed {
+        if value == 0. || !value.is_finite() {
+            return Fixed::zero(bits);
+        }
+        let (mantissa, exponent) = decompose_f64(value);
+        let shift = exponent + bits as i32;
+        let mantissa = if shift >= 0 {
+            BigInt::from(mantissa) << shift as usize
+        } else {
+            BigInt::from(mantissa) >> (-shift) as usize
+        };
+        Self { mantissa, bits }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let excess = self.mantissa.bits() as i32 - CONVERSION_BITS as i32;
+        if excess > 0 {
+            let truncated = (&self.mantissa >> excess as usize).to_f64().unwrap_or(0.);
+            scale_f64(truncated, excess - self.bits as i32)
+        } else {
+            scale_f64(self.mantissa.to_f64().unwrap_or(0.), -(self.bits as i32))
+        }
+    }
+
+    pub fn get_bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn with_bits(&self, bits: u32) -> Fixed {
+        let mantissa = if bits >= self.bits {
+            &self.mantissa << (bits - self.bits) as usize
+        } else {
+            &self.mantissa >> (self.bits - bits) as usize
+        };
+        Self { mantissa, bits }
+    }
+
+    // A decimal number held with at least the given precision, and at least
+    // as many bits as its digits need. A center parsed for a viewport needs
+    // precision_for_step bits, or its rounding moves the view by many pixels.
+    pub fn parse(s: &str, bits: u32) -> Result<Fixed, MandelbrotError> {
+        let text = s.trim();
+        let (text, exponent) = match text.find(['e', 'E']) {
+            Some(i) => match text[i + 1..].parse::<i32>() {
+                Ok(e) => (&text[..i], e),
+                Err(_) => return Err(parse_error(s)),
+            },
+            None => (text, 0),
+        };
+        let (negative, text) = if let Some(rest) = text.strip_prefix('-') {
+            (true, rest)
+        } else {
+            (false, text.strip_prefix('+').unwrap_or(text))
+        };
+        let (int_part, frac_part) = match text.find('.') {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            None => (text, ""),
+        };
+        let digits = int_part.to_owned() + frac_part;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(parse_error(s));
+        }
+        let number = match BigInt::parse_bytes(digits.as_bytes(), 10) {
+            Some(n) => n,
+            None => return Err(parse_error(s)),
+        };
+
+        let scale = frac_part.len() as i64 - exponent as i64;
+        if scale.abs() > MAX_EXPONENT as i64 {
+            return Err(MandelbrotError::Parse(format!(
+                "exponent of '{}' out of range",
+                s
+            )));
+        }
+        let scale = scale as i32;
+        let bits = max(bits, precision_for_decimals(scale));
+        let mantissa = if scale >= 0 {
+            let divisor = num::pow(BigInt::from(10), scale as usize);
+            div_round(number << bits as usize, &divisor)
+        } else {
+            (num::pow(BigInt::from(10), (-scale) as usize) * number) << bits as usize
+        };
+        Ok(Self {
+            mantissa: if negative { -mantissa } else { mantissa },
+            bits,
+        })
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    pub fn square(&self) -> Fixed {
+        self * self
+    }
+
+    pub fn double(&self) -> Fixed {
+        Self {
+            mantissa: &self.mantissa << 1,
+            bits: self.bits,
+        }
+    }
+
+    fn aligned(&self, other: &Fixed) -> (BigInt, BigInt, u32) {
+        let bits = max(self.bits, other.bits);
+        (
+            self.with_bits(bits).mantissa,
+            other.with_bits(bits).mantissa,
+            bits,
+        )
+    }
+}
+
+impl Add for &Fixed {
+    type Output = Fixed;
+
+    fn add(self, other: &Fixed) -> Fixed {
+        let (a, b, bits) = self.aligned(other);
+        Fixed {
+            mantissa: a + b,
+            bits,
+        }
+    }
+}
+
+impl Sub for &Fixed {
+    type Output = Fixed;
+
+    fn sub(self, other: &Fixed) -> Fixed {
+        let (a, b, bits) = self.aligned(other);
+        Fixed {
+            mantissa: a - b,
+            bits,
+        }
+    }
+}
+
+impl Mul for &Fixed {
+    type Output = Fixed;
+
+    fn mul(self, other: &Fixed) -> Fixed {
+        let bits = max(self.bits, other.bits);
+        let product = &self.mantissa * &other.mantissa;
+        let product_bits = self.bits + other.bits;
+        Fixed {
+            mantissa: product >> (product_bits - bits) as usize,
+            bits,
+        }
+    }
+}
+
+impl Neg for &Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed {
+            mantissa: -&self.mantissa,
+            bits: self.bits,
+        }
+    }
+}
+
+impl FromStr for Fixed {
+    type Err = MandelbrotError;
+
+    fn from_str(s: &str) -> Result<Fixed, MandelbrotError> {
+        Fixed::parse(s, 0)
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = max(1, (self.bits as f64 / LOG2_10) as usize - 1);
+        let magnitude = self.mantissa.abs() * num::pow(BigInt::from(10), digits);
+        let scaled = div_round(magnitude, &(BigInt::from(1) << self.bits as usize));
+        let mut text = scaled.to_str_radix(10);
+        if text.len() <= digits {
+            text = "0".repeat(digits + 1 - text.len()) + &text;
+        }
+        let (int_part, frac_part) = text.split_at(text.len() - digits);
+        let frac_part = frac_part.trim_end_matches('0');
+        if self.mantissa.is_negative() && !scaled.is_zero() {
+            write!(f, "-")?;
+        }
+        if frac_part.is_empty() {
+            write!(f, "{}", int_part)
+        } else {
+            write!(f, "{}.{}", int_part, frac_part)
+        }
+    }
+}
+
+pub fn precision_for_step(step_size: f64) -> u32 {
+    let step_bits = -step_size.log2();
+    // a step of 0 would need infinitely many bits
+    if step_bits.is_finite() && step_bits > 0. {
+        max(DEFAULT_BITS, step_bits as u32 + PRECISION_MARGIN)
+    } else {
+        DEFAULT_BITS
+    }
+}
+
+fn precision_for_decimals(decimals: i32) -> u32 {
+    (max(0, decimals) as f64 * LOG2_10).ceil() as u32 + DEFAULT_BITS
+}
+
+fn div_round(numerator: BigInt, denominator: &BigInt) -> BigInt {
+    let doubled: BigInt = numerator * 2 + denominator;
+    doubled / (denominator * 2)
+}
+
+fn parse_error(text: &str) -> MandelbrotError {
+    MandelbrotError::Parse(format!("invalid decimal number '{}'", text))
+}
+
+fn decompose_f64(value: f64) -> (i64, i32) {
+    let raw = value.to_bits();
+    let sign = if raw >> 63 == 0 { 1 } else { -1 };
+    let exponent = ((raw >> 52) & 0x7ff) as i32;
+    let fraction = (raw & 0xf_ffff_ffff_ffff) as i64;
+    if exponent == 0 {
+        (sign * fraction, -1074)
+    } else {
+        (sign * (fraction | 0x10_0000_0000_0000), exponent - 1075)
+    }
+}
+
+fn scale_f64(value: f64, exponent: i32) -> f64 {
+    let mut result = value;
+    let mut remaining = exponent;
+    while remaining > 1000 {
+        result *= 2f64.powi(1000);
+        remaining -= 1000;
+    }
+    while remaining < -1000 {
+        result *= 2f64.powi(-1000);
+        remaining += 1000;
+    }
+    result * 2f64.powi(remaining)
+}
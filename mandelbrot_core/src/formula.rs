@@ -0,0 +1,176 @@
+use std::fmt;
+
+use num::Complex;
+
+use crate::kernel::BAILOUT;
+use crate::sample::{Sample, Tracker};
+
+const PERIODICITY_TOLERANCE: f64 = 1e-24;
+
+pub trait Formula: fmt::Debug + Send + Sync {
+    fn iterate(&self, z: Complex<f64>, previous: Complex<f64>, c: Complex<f64>) -> Complex<f64>;
+
+    fn degree(&self) -> f64 {
+        2.
+    }
+
+    // derivative of the iteration with respect to z, used for distance estimation
+    fn derivative(&self, _z: Complex<f64>) -> Option<Complex<f64>> {
+        None
+    }
+
+    // the double-double and perturbation kernels only implement z^2 + c
+    fn is_quadratic(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Quadratic;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Multibrot {
+    power: i32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RealMultibrot {
+    power: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BurningShip;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tricorn;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Phoenix {
+    p: Complex<f64>,
+}
+
+impl Multibrot {
+    pub fn new(power: i32) -> Multibrot {
+        Self { power }
+    }
+}
+
+impl RealMultibrot {
+    pub fn new(power: f64) -> RealMultibrot {
+        Self { power }
+    }
+}
+
+impl Phoenix {
+    pub fn new(p: [f64; 2]) -> Phoenix {
+        Self {
+            p: Complex::new(p[0], p[1]),
+        }
+    }
+}
+
+impl Formula for Quadratic {
+    fn iterate(&self, z: Complex<f64>, _previous: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        z * z + c
+    }
+
+    fn derivative(&self, z: Complex<f64>) -> Option<Complex<f64>> {
+        Some(z * 2.)
+    }
+
+    fn is_quadratic(&self) -> bool {
+        true
+    }
+}
+
+impl Formula for Multibrot {
+    fn iterate(&self, z: Complex<f64>, _previous: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        z.powi(self.power) + c
+    }
+
+    fn degree(&self) -> f64 {
+        self.power as f64
+    }
+
+    fn derivative(&self, z: Complex<f64>) -> Option<Complex<f64>> {
+        Some(z.powi(self.power - 1) * self.power as f64)
+    }
+
+    fn is_quadratic(&self) -> bool {
+        self.power == 2
+    }
+}
+
+impl Formula for RealMultibrot {
+    fn iterate(&self, z: Complex<f64>, _previous: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        z.powf(self.power) + c
+    }
+
+    fn degree(&self) -> f64 {
+        self.power
+    }
+
+    fn derivative(&self, z: Complex<f64>) -> Option<Complex<f64>> {
+        Some(z.powf(self.power - 1.) * self.power)
+    }
+}
+
+impl Formula for BurningShip {
+    fn iterate(&self, z: Complex<f64>, _previous: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let folded = Complex::new(z.re.abs(), z.im.abs());
+        folded * folded + c
+    }
+}
+
+impl Formula for Tricorn {
+    fn iterate(&self, z: Complex<f64>, _previous: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        let conj = z.conj();
+        conj * conj + c
+    }
+}
+
+impl Formula for Phoenix {
+    fn iterate(&self, z: Complex<f64>, previous: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        z * z + c + self.p * previous
+    }
+}
+
+// With periodicity checking the orbit is compared against a saved state whose
+// distance doubles every time it is reached (Brent), so a point caught in an
+// attracting cycle is reported as interior without running to max_depth.
+pub fn check_escape<F: Formula + ?Sized>(
+    formula: &F,
+    start: Complex<f64>,
+    c: Complex<f64>,
+    max_depth: u32,
+    periodicity: bool,
+    mut tracker: Tracker,
+) -> Sample {
+    let mut z = start;
+    let mut previous = Complex::new(0., 0.);
+    let (mut saved, mut saved_previous) = (z, previous);
+    let (mut power, mut steps) = (1, 0);
+    for i in 0..max_depth {
+        tracker.advance(z, c);
+        let next = formula.iterate(z, previous, c);
+        previous = z;
+        z = next;
+        if z.norm_sqr() >= BAILOUT {
+            return tracker.escaped(i, z);
+        }
+        if periodicity {
+            let difference = (z - saved).norm_sqr() + (previous - saved_previous).norm_sqr();
+            if difference < PERIODICITY_TOLERANCE {
+                break;
+            }
+            steps += 1;
+            if steps == power {
+                saved = z;
+                saved_previous = previous;
+                power *= 2;
+                steps = 0;
+            }
+        }
+    }
+    tracker.interior(z, previous, c)
+}
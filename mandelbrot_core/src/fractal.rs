@@ -0,0 +1,17 @@
+use num::Complex;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fractal {
+    Mandelbrot,
+    Julia([f64; 2]),
+}
+
+impl Fractal {
+    // starting value z0 and parameter c of the orbit belonging to a point
+    pub fn start(self, point: Complex<f64>) -> (Complex<f64>, Complex<f64>) {
+        match self {
+            Fractal::Mandelbrot => (Complex::new(0., 0.), point),
+            Fractal::Julia(c) => (point, Complex::new(c[0], c[1])),
+        }
+    }
+}
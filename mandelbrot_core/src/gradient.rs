@@ -0,0 +1,179 @@
+use std::cmp::Ordering;
+
+use palette::{Lab, Lch, LinSrgb, Mix, Pixel, Srgb, Xyz};
+use rand::thread_rng;
+use rand::Rng;
+
+use crate::ColorBucket;
+
+pub const DEFAULT_CYCLE_LENGTH: f64 = 100.;
+// fully saturated hues at 60 degree steps, between which the hue circle is
+// linear in sRGB
+const RAINBOW: [[u8; 3]; 6] = [
+    [255, 0, 0],
+    [255, 255, 0],
+    [0, 255, 0],
+    [0, 255, 255],
+    [0, 0, 255],
+    [255, 0, 255],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Srgb,
+    LinearRgb,
+    Lab,
+    Lch,
+}
+
+// position within one cycle of the gradient, between 0 and 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub position: f64,
+    pub color: [u8; 3],
+}
+
+// Colors of the iteration counts: the stops repeat every cycle_length
+// iterations, shifted by offset cycles, with the last stop blending back into
+// the first one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<ColorStop>,
+    interpolation: Interpolation,
+    cycle_length: f64,
+    offset: f64,
+}
+
+impl ColorStop {
+    pub fn new(position: f64, color: [u8; 3]) -> ColorStop {
+        Self { position, color }
+    }
+}
+
+impl Gradient {
+    pub fn new(mut stops: Vec<ColorStop>, interpolation: Interpolation) -> Gradient {
+        stops.sort_by(|a, b| {
+            a.position
+                .partial_cmp(&b.position)
+                .unwrap_or(Ordering::Equal)
+        });
+        Self {
+            stops,
+            interpolation,
+            cycle_length: DEFAULT_CYCLE_LENGTH,
+            offset: 0.,
+        }
+    }
+
+    // the hue circle at full saturation and value, starting at the given hue
+    pub fn rainbow(hue: f64) -> Gradient {
+        let stops = RAINBOW
+            .iter()
+            .enumerate()
+            .map(|(i, color)| ColorStop::new(i as f64 / RAINBOW.len() as f64, *color))
+            .collect();
+        let mut gradient = Gradient::new(stops, Interpolation::Srgb);
+        gradient.set_offset(hue);
+        gradient
+    }
+
+    pub fn random_rainbow() -> Gradient {
+        Gradient::rainbow(thread_rng().gen_range(0., 1.))
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    pub fn set_cycle_length(&mut self, cycle_length: f64) {
+        self.cycle_length = cycle_length;
+    }
+
+    pub fn set_offset(&mut self, offset: f64) {
+        self.offset = offset;
+    }
+
+    pub fn get_stops(&self) -> &[ColorStop] {
+        &self.stops
+    }
+    pub fn get_interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+    pub fn get_cycle_length(&self) -> f64 {
+        self.cycle_length
+    }
+    pub fn get_offset(&self) -> f64 {
+        self.offset
+    }
+
+    pub fn color(&self, v: f64) -> ColorBucket {
+        self.at(v / self.cycle_length + self.offset)
+    }
+
+    // color at a position along the cycle, wrapping around outside of 0..1
+    pub fn at(&self, position: f64) -> ColorBucket {
+        let position = position.rem_euclid(1.);
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return ColorBucket::new([0, 0, 0]),
+        };
+        let next = self.stops.partition_point(|s| s.position <= position);
+        let (a, b, start, end) = if next == 0 {
+            (last, first, last.position - 1., first.position)
+        } else if next == self.stops.len() {
+            (last, first, last.position, first.position + 1.)
+        } else {
+            let (a, b) = (&self.stops[next - 1], &self.stops[next]);
+            (a, b, a.position, b.position)
+        };
+        let factor = if end > start {
+            ((position - start) / (end - start)) as f32
+        } else {
+            0.
+        };
+        ColorBucket::new(self.mix(a.color, b.color, factor))
+    }
+
+    fn mix(&self, a: [u8; 3], b: [u8; 3], factor: f32) -> [u8; 3] {
+        let a = Srgb::from_raw(&a).into_format::<f32>();
+        let b = Srgb::from_raw(&b).into_format::<f32>();
+        let mixed = match self.interpolation {
+            Interpolation::Srgb => {
+                let a: [f32; 3] = a.into_raw();
+                let b: [f32; 3] = b.into_raw();
+                Srgb::new(
+                    a[0] + (b[0] - a[0]) * factor,
+                    a[1] + (b[1] - a[1]) * factor,
+                    a[2] + (b[2] - a[2]) * factor,
+                )
+            }
+            Interpolation::LinearRgb => {
+                Srgb::from_linear(a.into_linear().mix(&b.into_linear(), factor))
+            }
+            Interpolation::Lab => {
+                let mixed = to_lab(a).mix(&to_lab(b), factor);
+                Srgb::from_linear(LinSrgb::from(Xyz::from(mixed)))
+            }
+            Interpolation::Lch => {
+                let mixed = Lch::from(to_lab(a)).mix(&Lch::from(to_lab(b)), factor);
+                Srgb::from_linear(LinSrgb::from(Xyz::from(Lab::from(mixed))))
+            }
+        };
+        let mixed: [f32; 3] = mixed.into_raw();
+        let mut color = [0; 3];
+        for (c, m) in color.iter_mut().zip(mixed.iter()) {
+            *c = (m.clamp(0., 1.) * 255.).round() as u8;
+        }
+        color
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient::random_rainbow()
+    }
+}
+
+fn to_lab(color: Srgb) -> Lab {
+    Lab::from(Xyz::from(color.into_linear()))
+}
@@ -0,0 +1,300 @@
+use std::fs;
+use std::path::Path;
+
+use crate::{ColorStop, Gradient, Interpolation, MandelbrotError};
+
+// colors written to the formats holding evenly spaced colors only, for
+// gradients whose stops are not
+const SAMPLED_COLORS: usize = 256;
+// entries of the Kalles Fraktaler palette, over which its key colors are spread
+const KF_PALETTE_SIZE: f64 = 1024.;
+const GGR_HEADER: &str = "GIMP Gradient";
+// values of a GIMP segment, newer files append two endpoint color types
+const GGR_SEGMENT_VALUES: usize = 13;
+
+// The format is chosen by the extension: Fractint .map, GIMP .ggr and the
+// Kalles Fraktaler palette (.kfp) and location (.kfr) files.
+pub fn read_gradient(path: &str) -> Result<Gradient, MandelbrotError> {
+    let parse = match extension(path).as_str() {
+        "map" => parse_map,
+        "ggr" => parse_ggr,
+        "kfp" | "kfr" => parse_kfp,
+        other => return Err(MandelbrotError::UnsupportedFormat(other.to_string())),
+    };
+    parse(&fs::read_to_string(path)?)
+}
+
+pub fn write_gradient(path: &str, gradient: &Gradient) -> Result<(), MandelbrotError> {
+    let text = match extension(path).as_str() {
+        "map" => format_map(gradient),
+        "ggr" => format_ggr(gradient),
+        "kfp" => format_kfp(gradient),
+        other => return Err(MandelbrotError::UnsupportedFormat(other.to_string())),
+    };
+    fs::write(path, text)?;
+    Ok(())
+}
+
+// One "red green blue" line per color, anything after the three components
+// is a comment.
+pub fn parse_map(text: &str) -> Result<Gradient, MandelbrotError> {
+    let mut colors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let components: Vec<&str> = line.split_whitespace().take(3).collect();
+        if !components.is_empty() {
+            colors.push(parse_color(&components, i + 1)?);
+        }
+    }
+    if colors.is_empty() {
+        return Err(MandelbrotError::Palette("no colors".to_string()));
+    }
+    Ok(Gradient::new(evenly_spaced(colors), Interpolation::Srgb))
+}
+
+pub fn format_map(gradient: &Gradient) -> String {
+    let mut text = String::new();
+    for color in spaced_colors(gradient) {
+        text += &format!("{:3} {:3} {:3}\n", color[0], color[1], color[2]);
+    }
+    text
+}
+
+// A header, an optional name, the number of segments and one line per segment:
+// left, middle and right position, left and right RGBA color, blending and
+// coloring type. Segments become stops at both ends and at an off-center
+// middle. Curved blending, HSV coloring and alpha are approximated by linear
+// blending of the opaque colors.
+pub fn parse_ggr(text: &str) -> Result<Gradient, MandelbrotError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    match lines.next() {
+        Some((_, GGR_HEADER)) => (),
+        _ => return Err(line_error(1, format!("expected '{}'", GGR_HEADER))),
+    }
+    let mut next = lines.next();
+    if let Some((_, line)) = next {
+        if line.starts_with("Name:") {
+            next = lines.next();
+        }
+    }
+    let count: usize = match next {
+        Some((i, line)) => line
+            .parse()
+            .map_err(|_| line_error(i, format!("invalid segment count '{}'", line)))?,
+        None => {
+            return Err(MandelbrotError::Palette(
+                "missing segment count".to_string(),
+            ))
+        }
+    };
+
+    let mut stops: Vec<ColorStop> = Vec::new();
+    for segment in 0..count {
+        let (i, line) = lines.next().ok_or_else(|| {
+            MandelbrotError::Palette(format!("expected {} segments, found {}", count, segment))
+        })?;
+        let values = line
+            .split_whitespace()
+            .map(|v| v.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| line_error(i, format!("invalid segment '{}'", line)))?;
+        if values.len() < GGR_SEGMENT_VALUES {
+            return Err(line_error(
+                i,
+                format!(
+                    "expected {} values, found {}",
+                    GGR_SEGMENT_VALUES,
+                    values.len()
+                ),
+            ));
+        }
+        let (left, middle, right) = (values[0], values[1], values[2]);
+        if !(0. <= left && left <= middle && middle <= right && right <= 1.) {
+            return Err(line_error(i, "segment positions out of order".to_string()));
+        }
+        let left_color = unit_color(&values[3..6]);
+        let right_color = unit_color(&values[7..10]);
+        push_stop(&mut stops, ColorStop::new(left, left_color));
+        if (middle - (left + right) / 2.).abs() > 1e-6 {
+            let mut color = [0; 3];
+            for (c, (l, r)) in color
+                .iter_mut()
+                .zip(left_color.iter().zip(right_color.iter()))
+            {
+                *c = ((*l as f64 + *r as f64) / 2.).round() as u8;
+            }
+            push_stop(&mut stops, ColorStop::new(middle, color));
+        }
+        push_stop(&mut stops, ColorStop::new(right, right_color));
+    }
+    Ok(Gradient::new(stops, Interpolation::Srgb))
+}
+
+pub fn format_ggr(gradient: &Gradient) -> String {
+    // the segments have to cover 0..1, where the gradient wraps around
+    let mut stops = gradient.get_stops().to_vec();
+    if stops.first().is_some_and(|s| s.position > 0.) {
+        stops.insert(0, ColorStop::new(0., gradient.at(0.).get_color()));
+    }
+    if stops.last().is_some_and(|s| s.position < 1.) {
+        stops.push(ColorStop::new(1., gradient.at(1.).get_color()));
+    }
+    let segments = stops.len().saturating_sub(1);
+    let mut text = format!("{}\n{}\n", GGR_HEADER, segments);
+    for pair in stops.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        text += &format!(
+            "{:.6} {:.6} {:.6} {} {} 0 0\n",
+            a.position,
+            (a.position + b.position) / 2.,
+            b.position,
+            format_unit_color(a.color),
+            format_unit_color(b.color)
+        );
+    }
+    text
+}
+
+// "Key: value" lines, of which Colors, IterDiv and ColorOffset are used. The
+// key colors are spread evenly over the palette, which is passed through once
+// every 1024 * IterDiv iterations and shifted by ColorOffset entries.
+pub fn parse_kfp(text: &str) -> Result<Gradient, MandelbrotError> {
+    let mut colors = None;
+    let mut iteration_divisor = 1.;
+    let mut offset = 0.;
+    for (i, line) in text.lines().enumerate() {
+        let (key, value) = match line.find(':') {
+            Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+            None => continue,
+        };
+        match key {
+            "Colors" => {
+                let components: Vec<&str> = value
+                    .split(',')
+                    .map(|c| c.trim())
+                    .filter(|c| !c.is_empty())
+                    .collect();
+                let triples = components.chunks_exact(3);
+                if components.is_empty() || !triples.remainder().is_empty() {
+                    return Err(line_error(
+                        i + 1,
+                        format!("expected color triples, found {} values", components.len()),
+                    ));
+                }
+                let parsed = triples
+                    .map(|c| parse_color(c, i + 1))
+                    .collect::<Result<Vec<[u8; 3]>, _>>()?;
+                colors = Some(parsed);
+            }
+            "IterDiv" => {
+                iteration_divisor = parse_number(value, i + 1)?;
+                if iteration_divisor <= 0. {
+                    return Err(line_error(i + 1, "IterDiv must be positive".to_string()));
+                }
+            }
+            "ColorOffset" => offset = parse_number(value, i + 1)?,
+            _ => (),
+        }
+    }
+    let colors = colors.ok_or_else(|| MandelbrotError::Palette("no Colors".to_string()))?;
+    let mut gradient = Gradient::new(evenly_spaced(colors), Interpolation::Srgb);
+    gradient.set_cycle_length(KF_PALETTE_SIZE * iteration_divisor);
+    gradient.set_offset(offset / KF_PALETTE_SIZE);
+    Ok(gradient)
+}
+
+pub fn format_kfp(gradient: &Gradient) -> String {
+    let mut colors = String::new();
+    for color in spaced_colors(gradient) {
+        colors += &format!("{},{},{},", color[0], color[1], color[2]);
+    }
+    format!(
+        "Colors: {}\nIterDiv: {}\nColorOffset: {}\n",
+        colors,
+        gradient.get_cycle_length() / KF_PALETTE_SIZE,
+        (gradient.get_offset() * KF_PALETTE_SIZE).round()
+    )
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+fn line_error(line: usize, message: String) -> MandelbrotError {
+    MandelbrotError::Palette(format!("line {}: {}", line, message))
+}
+
+fn parse_color(components: &[&str], line: usize) -> Result<[u8; 3], MandelbrotError> {
+    if components.len() != 3 {
+        return Err(line_error(
+            line,
+            format!("expected 3 color components, found {}", components.len()),
+        ));
+    }
+    let mut color = [0; 3];
+    for (c, text) in color.iter_mut().zip(components.iter()) {
+        *c = text
+            .parse()
+            .map_err(|_| line_error(line, format!("invalid color component '{}'", text)))?;
+    }
+    Ok(color)
+}
+
+fn parse_number(text: &str, line: usize) -> Result<f64, MandelbrotError> {
+    text.parse()
+        .map_err(|_| line_error(line, format!("invalid number '{}'", text)))
+}
+
+fn unit_color(components: &[f64]) -> [u8; 3] {
+    let mut color = [0; 3];
+    for (c, v) in color.iter_mut().zip(components.iter()) {
+        *c = (v.clamp(0., 1.) * 255.).round() as u8;
+    }
+    color
+}
+
+fn format_unit_color(color: [u8; 3]) -> String {
+    format!(
+        "{:.6} {:.6} {:.6} 1.000000",
+        color[0] as f64 / 255.,
+        color[1] as f64 / 255.,
+        color[2] as f64 / 255.
+    )
+}
+
+fn evenly_spaced(colors: Vec<[u8; 3]>) -> Vec<ColorStop> {
+    let count = colors.len() as f64;
+    colors
+        .into_iter()
+        .enumerate()
+        .map(|(i, color)| ColorStop::new(i as f64 / count, color))
+        .collect()
+}
+
+// the colors of the stops if they are evenly spaced, samples otherwise
+fn spaced_colors(gradient: &Gradient) -> Vec<[u8; 3]> {
+    let stops = gradient.get_stops();
+    let even = stops
+        .iter()
+        .enumerate()
+        .all(|(i, s)| (s.position - i as f64 / stops.len() as f64).abs() < 1e-9);
+    if even && !stops.is_empty() {
+        stops.iter().map(|s| s.color).collect()
+    } else {
+        (0..SAMPLED_COLORS)
+            .map(|i| gradient.at(i as f64 / SAMPLED_COLORS as f64).get_color())
+            .collect()
+    }
+}
+
+fn push_stop(stops: &mut Vec<ColorStop>, stop: ColorStop) {
+    if stops.last() != Some(&stop) {
+        stops.push(stop);
+    }
+}
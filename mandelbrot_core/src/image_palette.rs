@@ -0,0 +1,166 @@
+use palette::{Lab, LinSrgb, Pixel, Srgb, Xyz};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{ColorStop, Gradient, Interpolation, MandelbrotError};
+
+// images are scaled down to fit this size before their colors are clustered
+const THUMBNAIL_SIZE: u32 = 128;
+const MAX_ITERATIONS: usize = 64;
+// seed for picking the initial centers, an image always gives the same gradient
+const SEED: u64 = 0;
+
+pub fn extract_gradient(path: &str, colors: usize) -> Result<Gradient, MandelbrotError> {
+    let image = image::open(path)?
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb();
+    let pixels: Vec<[u8; 3]> = image.pixels().map(|p| p.data).collect();
+    Ok(gradient_from_colors(&pixels, colors))
+}
+
+// Clusters the colors with k-means in Lab and orders the cluster centers along
+// a short closed path starting at the darkest one, so that the gradient also
+// wraps around smoothly. There are fewer stops than requested if the colors
+// do not have enough distinct values.
+pub fn gradient_from_colors(colors: &[[u8; 3]], count: usize) -> Gradient {
+    let points: Vec<[f32; 3]> = colors.iter().map(|c| to_lab(*c)).collect();
+    let centers = k_means(&points, count);
+    let path = closed_path(&centers);
+    let stops = path
+        .iter()
+        .enumerate()
+        .map(|(i, c)| ColorStop::new(i as f64 / path.len() as f64, from_lab(centers[*c])))
+        .collect();
+    Gradient::new(stops, Interpolation::Lab)
+}
+
+fn k_means(points: &[[f32; 3]], count: usize) -> Vec<[f32; 3]> {
+    if points.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    // k-means++: further centers are picked with a probability proportional to
+    // the squared distance to the closest center picked so far
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut centers = vec![points[rng.gen_range(0, points.len())]];
+    let mut nearest: Vec<f32> = points.iter().map(|p| distance(p, &centers[0])).collect();
+    while centers.len() < count {
+        let total: f32 = nearest.iter().sum();
+        if total <= 0. {
+            break;
+        }
+        let mut target = rng.gen_range(0., total);
+        let index = nearest
+            .iter()
+            .position(|d| {
+                target -= d;
+                target < 0.
+            })
+            .unwrap_or(points.len() - 1);
+        let center = points[index];
+        for (n, p) in nearest.iter_mut().zip(points.iter()) {
+            *n = n.min(distance(p, &center));
+        }
+        centers.push(center);
+    }
+
+    let mut assignment = vec![usize::MAX; points.len()];
+    let mut sizes = vec![0; centers.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (a, p) in assignment.iter_mut().zip(points.iter()) {
+            let closest = (0..centers.len())
+                .min_by(|&i, &j| {
+                    distance(p, &centers[i])
+                        .partial_cmp(&distance(p, &centers[j]))
+                        .unwrap()
+                })
+                .unwrap();
+            if closest != *a {
+                *a = closest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let mut sums = vec![[0.; 3]; centers.len()];
+        sizes = vec![0; centers.len()];
+        for (a, p) in assignment.iter().zip(points.iter()) {
+            for (s, v) in sums[*a].iter_mut().zip(p.iter()) {
+                *s += v;
+            }
+            sizes[*a] += 1;
+        }
+        for ((center, sum), size) in centers.iter_mut().zip(sums).zip(sizes.iter()) {
+            if *size > 0 {
+                for (c, s) in center.iter_mut().zip(sum.iter()) {
+                    *c = s / *size as f32;
+                }
+            }
+        }
+    }
+    centers
+        .into_iter()
+        .zip(sizes)
+        .filter(|(_, size)| *size > 0)
+        .map(|(center, _)| center)
+        .collect()
+}
+
+// nearest neighbour path, shortened by reversing sections while that helps
+fn closed_path(centers: &[[f32; 3]]) -> Vec<usize> {
+    let length = |a: usize, b: usize| distance(&centers[a], &centers[b]).sqrt();
+    let mut remaining: Vec<usize> = (0..centers.len()).collect();
+    remaining.sort_by(|&a, &b| centers[a][0].partial_cmp(&centers[b][0]).unwrap());
+    let mut path = Vec::with_capacity(centers.len());
+    if !remaining.is_empty() {
+        path.push(remaining.remove(0));
+    }
+    while let Some(&last) = path.last() {
+        let next = (0..remaining.len()).min_by(|&i, &j| {
+            length(last, remaining[i])
+                .partial_cmp(&length(last, remaining[j]))
+                .unwrap()
+        });
+        match next {
+            Some(i) => path.push(remaining.remove(i)),
+            None => break,
+        }
+    }
+
+    let n = path.len();
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..n.saturating_sub(2) {
+            for j in i + 2..n {
+                let (a, b, c, d) = (path[i], path[i + 1], path[j], path[(j + 1) % n]);
+                if d != a && length(a, c) + length(b, d) < length(a, b) + length(c, d) - 1e-3 {
+                    path[i + 1..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+    path
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    a.iter().zip(b.iter()).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+fn to_lab(color: [u8; 3]) -> [f32; 3] {
+    let linear = Srgb::from_raw(&color).into_format::<f32>().into_linear();
+    let (l, a, b) = Lab::from(Xyz::from(linear)).into_components();
+    [l, a, b]
+}
+
+fn from_lab(lab: [f32; 3]) -> [u8; 3] {
+    let linear = LinSrgb::from(Xyz::from(Lab::new(lab[0], lab[1], lab[2])));
+    let rgb: [f32; 3] = Srgb::from_linear(linear).into_raw();
+    let mut color = [0; 3];
+    for (c, v) in color.iter_mut().zip(rgb.iter()) {
+        *c = (v.clamp(0., 1.) * 255.).round() as u8;
+    }
+    color
+}
@@ -0,0 +1,39 @@
+use num::Complex;
+
+pub const BAILOUT: f64 = 65536.;
+
+const DOUBLE_THRESHOLD: f64 = 1e-13;
+const DOUBLE_DOUBLE_THRESHOLD: f64 = 1e-29;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    Auto,
+    Double,
+    DoubleDouble,
+    Perturbation,
+}
+
+impl Kernel {
+    pub fn resolve(self, step_size: f64) -> Kernel {
+        match self {
+            Kernel::Auto if step_size >= DOUBLE_THRESHOLD => Kernel::Double,
+            Kernel::Auto if step_size >= DOUBLE_DOUBLE_THRESHOLD => Kernel::DoubleDouble,
+            Kernel::Auto => Kernel::Perturbation,
+            k => k,
+        }
+    }
+}
+
+// Normalized iteration count, continuous across escape iterations for the
+// large bailout radius used by all kernels
+pub fn smooth_escape(iteration: u32, norm_sqr: f64, degree: f64) -> f64 {
+    let smooth = iteration as f64 + 1. - (0.5 * norm_sqr.ln()).ln() / degree.ln();
+    smooth.max(0.)
+}
+
+// c lies inside the main cardioid or the period-2 bulb of z^2 + c
+pub fn in_main_components(c: Complex<f64>) -> bool {
+    let y_sqr = c.im * c.im;
+    let q = (c.re - 0.25) * (c.re - 0.25) + y_sqr;
+    q * (q + c.re - 0.25) < 0.25 * y_sqr || (c.re + 1.) * (c.re + 1.) + y_sqr < 1. / 16.
+}
@@ -5,15 +5,59 @@ extern crate num;
 extern crate rayon;
 extern crate palette;
 extern crate image;
+extern crate png;
+extern crate deflate;
 extern crate histogram;
 extern crate rand;
 
 pub mod mandelbrot;
 pub mod color_bucket;
 pub mod mandelbrot_error;
+pub mod fixed;
+pub mod coordinate;
+pub mod viewport;
+pub mod kernel;
+pub mod double_double;
+pub mod coloring;
+pub mod fractal;
+pub mod formula;
+pub mod newton;
+pub mod sample;
+pub mod strategy;
+pub mod progressive;
+pub mod tile_cache;
+pub mod supersampling;
+pub mod gradient;
+pub mod gradient_file;
+pub mod image_palette;
+pub mod orbit_trap;
+pub mod buddhabrot;
+pub mod lyapunov;
+mod perturbation;
 mod snapshot;
 
 pub use self::mandelbrot::Mandelbrot;
 pub use self::color_bucket::ColorBucket;
 pub use self::mandelbrot_error::MandelbrotError;
+pub use self::fixed::Fixed;
+pub use self::coordinate::Coordinate;
+pub use self::viewport::Viewport;
+pub use self::kernel::Kernel;
+pub use self::double_double::DoubleDouble;
+pub use self::coloring::{Averaging, ColorMode, InteriorMode, Lighting, Overlay};
+pub use self::fractal::Fractal;
+pub use self::formula::Formula;
+pub use self::newton::{Newton, Polynomial};
+pub use self::sample::Sample;
+pub use self::strategy::Strategy;
+pub use self::progressive::{CancelToken, RenderEvent};
+pub use self::tile_cache::{TileCache, TileKey, TileSamples};
+pub use self::supersampling::Supersampling;
+pub use self::gradient::{ColorStop, Gradient, Interpolation};
+pub use self::gradient_file::{read_gradient, write_gradient};
+pub use self::image_palette::extract_gradient;
+pub use self::orbit_trap::{ImageTrap, OrbitTrap};
+pub use self::buddhabrot::{Buddhabrot, Orbits};
+pub use self::lyapunov::Lyapunov;
+pub use self::snapshot::PngStream;
 use self::snapshot::snapshot;
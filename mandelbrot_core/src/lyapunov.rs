@@ -0,0 +1,120 @@
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{MandelbrotError, Sample, Viewport};
+
+const DEFAULT_WARMUP: u32 = 200;
+const DEFAULT_ITERATIONS: u32 = 1000;
+const START: f64 = 0.5;
+// stable exponents are handed to the colorizer as -exponent * STABILITY_SCALE
+// iterations, capped at MAX_STABILITY for the superstable points at -infinity
+const STABILITY_SCALE: f64 = 50.;
+pub const MAX_STABILITY: u32 = 500;
+
+// Lyapunov exponent of the logistic map x -> r x (1 - x), with r switching
+// between a (real axis) and b (imaginary axis) in a repeating sequence such
+// as "AB". Stable pixels come out as escaping ones, their exponent as the
+// iteration count; chaotic ones are interior points with the exponent as
+// their magnitude.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lyapunov {
+    // true where the sequence takes b
+    sequence: Vec<bool>,
+    warmup: u32,
+    iterations: u32,
+}
+
+impl Lyapunov {
+    pub fn new(sequence: &str) -> Result<Lyapunov, MandelbrotError> {
+        let sequence = sequence
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'A' => Ok(false),
+                'B' => Ok(true),
+                _ => Err(MandelbrotError::Parse(format!(
+                    "invalid sequence '{}', expected A and B only",
+                    sequence
+                ))),
+            })
+            .collect::<Result<Vec<bool>, _>>()?;
+        if sequence.is_empty() {
+            return Err(MandelbrotError::Parse("empty sequence".to_string()));
+        }
+        Ok(Self {
+            sequence,
+            warmup: DEFAULT_WARMUP,
+            iterations: DEFAULT_ITERATIONS,
+        })
+    }
+
+    pub fn set_warmup(&mut self, warmup: u32) {
+        self.warmup = warmup;
+    }
+
+    pub fn set_iterations(&mut self, iterations: u32) {
+        self.iterations = iterations;
+    }
+
+    pub fn get_sequence(&self) -> String {
+        self.sequence
+            .iter()
+            .map(|b| if *b { 'B' } else { 'A' })
+            .collect()
+    }
+    pub fn get_warmup(&self) -> u32 {
+        self.warmup
+    }
+    pub fn get_iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    // the exponent averaged over the iterations after the warmup, not finite
+    // where the orbit leaves [0, 1]
+    pub fn exponent(&self, a: f64, b: f64) -> f64 {
+        let rate = |n: u32| {
+            if self.sequence[n as usize % self.sequence.len()] {
+                b
+            } else {
+                a
+            }
+        };
+        let mut x = START;
+        for n in 0..self.warmup {
+            x *= rate(n) * (1. - x);
+        }
+        let mut sum = 0.;
+        for n in self.warmup..self.warmup + self.iterations {
+            let r = rate(n);
+            x *= r * (1. - x);
+            sum += (r * (1. - 2. * x)).abs().ln();
+        }
+        sum / self.iterations as f64
+    }
+
+    pub fn sample(&self, a: f64, b: f64) -> Sample {
+        let exponent = self.exponent(a, b);
+        if exponent < 0. {
+            Sample {
+                iterations: Some((-exponent * STABILITY_SCALE).min(MAX_STABILITY as f64)),
+                ..Sample::default()
+            }
+        } else if exponent.is_finite() {
+            Sample {
+                magnitude: Some(exponent),
+                ..Sample::default()
+            }
+        } else {
+            Sample::default()
+        }
+    }
+
+    pub fn samples(&self, viewport: &Viewport, shape: [i32; 2]) -> Vec<Sample> {
+        viewport
+            .pixels(shape)
+            .par_iter()
+            .map(|p| {
+                let [a, b] = viewport.pixel_to_absolute(*p);
+                self.sample(a, b)
+            })
+            .collect()
+    }
+}
@@ -1,89 +1,235 @@
+use std::cmp::min;
+use std::collections::HashMap;
+
 use histogram::Histogram;
 use num::Complex;
 use palette;
 use palette::Pixel;
+use rand::{thread_rng, Rng};
 use rayon::iter::{IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
 
-use crate::{snapshot, ColorBucket, MandelbrotError};
+use crate::coloring::{Averaging, Colorizer, Equalization, Lighting, Overlay};
+use crate::double_double::{check_double_double, DoubleDouble};
+use crate::formula::{check_escape, Formula, Quadratic};
+use crate::gradient_file::read_gradient;
+use crate::kernel::in_main_components;
+use crate::lyapunov::MAX_STABILITY;
+use crate::perturbation::ReferenceOrbit;
+use crate::progressive::{render_progressive, CancelToken, RenderEvent, COARSE_SCALES};
+use crate::sample::{Channels, Statistic, Tracker};
+use crate::strategy::{subdivide, subdivide_block};
+use crate::supersampling::{average, differs, Supersampling};
+use crate::tile_cache::{TileCache, TileKey, TileSamples, TILE_SIZE};
+use crate::{
+    snapshot, Buddhabrot, ColorMode, Coordinate, Fractal, Gradient, InteriorMode, Kernel, Lyapunov,
+    MandelbrotError, OrbitTrap, PngStream, Sample, Strategy, Viewport,
+};
 
 const DEFAULT_STEP: f64 = 1. / 800.;
-const DEFAULT_COLOR_LOOP: i32 = 100;
+// missing tiles evaluated between two progress reports
+const TILE_BATCH: usize = 8;
+// rows of a frame rendered at a time
+const FRAME_BAND_ROWS: i32 = 64;
+// grid of samples estimating the iteration distribution of a frame
+const EQUALIZATION_SIZE: i32 = 128;
 // const DEFAULT_POS: [f64; 2] = [0.4379242413594627, -0.3418920843381163];
 const DEFAULT_POS: [f64; 2] = [0.41825764120184555, -0.34087020355542164];
 
 pub struct Mandelbrot {
-    step_size: f64,
-    center: [f64; 2],
+    viewport: Viewport,
+    fractal: Fractal,
+    formula: Box<dyn Formula>,
+    kernel: Kernel,
+    interior_checks: bool,
+    strategy: Strategy,
+    supersampling: Supersampling,
     depth: u32,
-    color_loop_depth: i32,
-    color_buckets: Vec<ColorBucket>,
+    color_mode: ColorMode,
+    interior_mode: InteriorMode,
+    lighting: Option<Lighting>,
+    overlay: Option<Overlay>,
+    orbit_trap: OrbitTrap,
+    averaging: Averaging,
+    gradient: Gradient,
 }
 
 impl Mandelbrot {
-    pub fn set_center(&mut self, new_center: [f64; 2]) {
-        self.center = new_center;
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    pub fn set_center(&mut self, new_center: Coordinate) {
+        self.viewport.set_center(new_center);
     }
 
     pub fn move_center(&mut self, units: [i32; 2]) {
-        self.center[0] += self.step_size * units[0] as f64;
-        self.center[1] += self.step_size * units[1] as f64;
+        self.viewport.move_center(units);
     }
 
     pub fn set_step_size(&mut self, value: f64) {
-        self.step_size = value;
+        self.viewport.set_step_size(value);
     }
 
     pub fn set_step_default(&mut self) {
-        self.step_size = DEFAULT_STEP;
+        self.viewport.set_step_size(DEFAULT_STEP);
     }
 
     pub fn zoom(&mut self, factor: f64) {
-        self.step_size *= factor;
+        self.viewport.zoom(factor);
+    }
+
+    pub fn set_fractal(&mut self, fractal: Fractal) {
+        self.fractal = fractal;
+    }
+
+    pub fn set_formula<F: Formula + 'static>(&mut self, formula: F) {
+        self.formula = Box::new(formula);
+    }
+
+    pub fn set_kernel(&mut self, kernel: Kernel) {
+        self.kernel = kernel;
+    }
+
+    pub fn set_interior_checks(&mut self, enabled: bool) {
+        self.interior_checks = enabled;
+    }
+
+    pub fn set_strategy(&mut self, strategy: Strategy) {
+        self.strategy = strategy;
+    }
+
+    pub fn set_supersampling(&mut self, supersampling: Supersampling) {
+        self.supersampling = supersampling;
+    }
+
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.color_mode = mode;
+    }
+
+    pub fn set_interior_mode(&mut self, mode: InteriorMode) {
+        self.interior_mode = mode;
+    }
+
+    pub fn set_lighting(&mut self, lighting: Option<Lighting>) {
+        self.lighting = lighting;
+    }
+
+    pub fn set_overlay(&mut self, overlay: Option<Overlay>) {
+        self.overlay = overlay;
+    }
+
+    pub fn set_orbit_trap(&mut self, trap: OrbitTrap) {
+        self.orbit_trap = trap;
+    }
+
+    pub fn set_averaging(&mut self, averaging: Averaging) {
+        self.averaging = averaging;
+    }
+
+    pub fn set_gradient(&mut self, gradient: Gradient) {
+        self.gradient = gradient;
+    }
+
+    pub fn load_gradient(&mut self, file_name: &str) -> Result<(), MandelbrotError> {
+        self.gradient = read_gradient(file_name)?;
+        Ok(())
     }
 
     pub fn set_depth(&mut self, value: u32) {
         self.depth = value;
-        self.update_buckets();
     }
 
     pub fn mod_depth(&mut self, value: u32) {
         self.set_depth(self.get_depth() + value);
     }
 
-    pub fn get_center(&self) -> [f64; 2] {
-        self.center
+    pub fn get_viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+    pub fn get_center(&self) -> &Coordinate {
+        self.viewport.get_center()
     }
     pub fn get_step_size(&self) -> f64 {
-        self.step_size
+        self.viewport.get_step_size()
     }
     pub fn get_depth(&self) -> u32 {
         self.depth
     }
+    pub fn get_fractal(&self) -> Fractal {
+        self.fractal
+    }
+    pub fn get_formula(&self) -> &dyn Formula {
+        self.formula.as_ref()
+    }
+    pub fn get_kernel(&self) -> Kernel {
+        self.kernel
+    }
+    pub fn get_interior_checks(&self) -> bool {
+        self.interior_checks
+    }
+    pub fn get_strategy(&self) -> Strategy {
+        self.strategy
+    }
+    pub fn get_supersampling(&self) -> Supersampling {
+        self.supersampling
+    }
+    pub fn get_color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+    pub fn get_interior_mode(&self) -> InteriorMode {
+        self.interior_mode
+    }
+    pub fn get_lighting(&self) -> Option<Lighting> {
+        self.lighting
+    }
+    pub fn get_overlay(&self) -> Option<Overlay> {
+        self.overlay
+    }
+    pub fn get_orbit_trap(&self) -> &OrbitTrap {
+        &self.orbit_trap
+    }
+    pub fn get_averaging(&self) -> Averaging {
+        self.averaging
+    }
+    pub fn get_gradient(&self) -> &Gradient {
+        &self.gradient
+    }
 
     pub fn randomize_start_color(&mut self) {
-        self.color_buckets.clear();
-        self.color_buckets.push(ColorBucket::random_bucket());
-        self.update_buckets();
+        self.gradient.set_offset(thread_rng().gen_range(0., 1.));
+    }
+
+    // hue circle from a random start, passed through every cycle_length iterations
+    pub fn randomize_continuos_color_ranged(&mut self, cycle_length: usize) {
+        let mut gradient = Gradient::random_rainbow();
+        gradient.set_cycle_length(cycle_length as f64);
+        self.gradient = gradient;
     }
 
     pub fn print_stats(&self) {
         info!(
-            "center = {} + j{}, step size = {}, depth = {}",
-            self.center[0], self.center[1], self.step_size, self.depth
+            "fractal = {:?}, formula = {:?}, center = {}, step size = {}, depth = {}",
+            self.fractal,
+            self.formula,
+            self.viewport.get_center(),
+            self.viewport.get_step_size(),
+            self.depth
         );
     }
 
     pub fn estimate_entropy(&self, shape: [i32; 2]) -> f32 {
         const EST_SIZE: i32 = 10;
         let mut histogram = Histogram::new();
+        let mut points = Vec::new();
         for y in -EST_SIZE / 2..EST_SIZE / 2 {
             for x in -EST_SIZE / 2..EST_SIZE / 2 {
-                let local_point = [x * shape[0] / EST_SIZE, y * shape[1] / EST_SIZE];
-                let abs_point = self.pixel_to_absolute(local_point);
-                match check_mandelbrot(&abs_point, self.depth) {
-                    Some(v) => histogram.increment(v as u64 + 1).unwrap(),
-                    None => histogram.increment(0).unwrap(),
-                }
+                points.push([x * shape[0] / EST_SIZE, y * shape[1] / EST_SIZE]);
+            }
+        }
+        for sample in self.check_points(&points, self.reference_orbit().as_ref()) {
+            match sample.iterations {
+                Some(v) => histogram.increment(v as u64 + 1).unwrap(),
+                None => histogram.increment(0).unwrap(),
             }
         }
 
@@ -103,6 +249,99 @@ impl Mandelbrot {
         snapshot(&pixels, shape, file_name)
     }
 
+    // orbit density of the viewport, the coloring settings are not used
+    pub fn snapshot_buddhabrot<F: FnMut(RenderEvent)>(
+        &self,
+        buddhabrot: &Buddhabrot,
+        file_name: &str,
+        shape: [i32; 2],
+        cancel: &CancelToken,
+        on_event: F,
+    ) -> Result<(), MandelbrotError> {
+        let pixels = buddhabrot.render_cancellable(
+            &self.viewport,
+            self.formula.as_ref(),
+            shape,
+            cancel,
+            on_event,
+        )?;
+        snapshot(&pixels, shape, file_name)
+    }
+
+    // Lyapunov exponents over the viewport, colored like the escape times
+    pub fn snapshot_lyapunov(
+        &self,
+        lyapunov: &Lyapunov,
+        file_name: &str,
+        shape: [i32; 2],
+    ) -> Result<(), MandelbrotError> {
+        let samples = lyapunov.samples(&self.viewport, shape);
+        let mut colorizer = self.plain_colorizer();
+        if self.color_mode == ColorMode::Histogram {
+            colorizer.set_equalization(Equalization::new(&samples, MAX_STABILITY));
+        }
+        let triplets: Vec<[u8; 3]> = samples.par_iter().map(|s| colorizer.colorize(s)).collect();
+        let mut pixels = Vec::with_capacity(triplets.len() * 3);
+        for rgb_triple in triplets {
+            pixels.extend_from_slice(&rgb_triple);
+        }
+        snapshot(&pixels, shape, file_name)
+    }
+
+    // For images too large to hold in memory: renders bands of band_height rows,
+    // computing the next band while the previous one is compressed and written.
+    pub fn snapshot_streamed<F: FnMut(RenderEvent)>(
+        &self,
+        file_name: &str,
+        shape: [i32; 2],
+        band_height: i32,
+        cancel: &CancelToken,
+        mut on_event: F,
+    ) -> Result<(), MandelbrotError> {
+        if band_height < 1 {
+            return Err(MandelbrotError::InvalidArgument(format!(
+                "band height {}",
+                band_height
+            )));
+        }
+        let mut stream = PngStream::create(file_name, shape)?;
+        let reference = self.reference_orbit();
+        let colorizer = self.colorizer(shape, None);
+        let (left, bottom) = (-shape[0] / 2, shape[1] - shape[1] / 2);
+        let render_band = |top: i32| -> Vec<[u8; 3]> {
+            let rows = min(band_height, bottom - top);
+            self.render_block(
+                [left, top],
+                [shape[0], rows],
+                &colorizer,
+                reference.as_ref(),
+            )
+        };
+
+        let tops: Vec<i32> = (-shape[1] / 2..bottom)
+            .step_by(band_height as usize)
+            .collect();
+        let mut band = match tops.first() {
+            Some(top) => render_band(*top),
+            None => Vec::new(),
+        };
+        for i in 0..tops.len() {
+            if cancel.is_cancelled() {
+                return Err(MandelbrotError::Cancelled);
+            }
+            let (written, next) = rayon::join(
+                || stream.write_rows(&band),
+                || tops.get(i + 1).map(|top| render_band(*top)),
+            );
+            written?;
+            on_event(RenderEvent::Progress((i + 1) as f64 / tops.len() as f64));
+            if let Some(next) = next {
+                band = next;
+            }
+        }
+        stream.finish()
+    }
+
     pub fn snapshot_sequence_zoomed(
         &mut self,
         count: usize,
@@ -121,87 +360,533 @@ impl Mandelbrot {
         Ok(())
     }
 
-    pub fn create_pixel_triplets(&self, shape: [i32; 2]) -> Vec<[u8; 3]> {
-        let values = self.create_values(shape);
-        values
+    pub fn snapshot_progressive<F: FnMut(RenderEvent)>(
+        &self,
+        file_name: &str,
+        shape: [i32; 2],
+        scales: &[i32],
+        cancel: &CancelToken,
+        on_event: F,
+    ) -> Result<(), MandelbrotError> {
+        let mut pixels = Vec::new();
+        for rgb_triple in self.render_progressive(shape, scales, cancel, on_event)? {
+            pixels.extend_from_slice(&rgb_triple);
+        }
+        snapshot(&pixels, shape, file_name)
+    }
+
+    // the passes of the given scales, PASS_SCALES for a coarse preview first
+    // or [1] for a single full resolution one
+    pub fn render_progressive<F: FnMut(RenderEvent)>(
+        &self,
+        shape: [i32; 2],
+        scales: &[i32],
+        cancel: &CancelToken,
+        on_event: F,
+    ) -> Result<Vec<[u8; 3]>, MandelbrotError> {
+        let reference = self.reference_orbit();
+        let colorizer = self.colorizer(shape, None);
+        render_progressive(
+            shape,
+            scales,
+            cancel,
+            |points| self.color_points(points, &colorizer, reference.as_ref()),
+            on_event,
+        )
+    }
+
+    // Like render_progressive, but only evaluates the tiles missing from the
+    // cache, with a coarse preview first if most of the view is new.
+    pub fn render_tiled<F: FnMut(RenderEvent)>(
+        &self,
+        shape: [i32; 2],
+        cache: &mut TileCache,
+        cancel: &CancelToken,
+        mut on_event: F,
+    ) -> Result<Vec<[u8; 3]>, MandelbrotError> {
+        let center = cache.anchor(&self.viewport, self.tile_context());
+        let zoom = self.viewport.get_step_size().to_bits();
+        let first = TileKey::containing(
+            zoom,
+            [
+                center[0] - (shape[0] / 2) as i64,
+                center[1] - (shape[1] / 2) as i64,
+            ],
+        );
+        let last = TileKey::containing(
+            zoom,
+            [
+                center[0] + (shape[0] / 2) as i64 - 1,
+                center[1] + (shape[1] / 2) as i64 - 1,
+            ],
+        );
+        let mut tiles = HashMap::new();
+        let mut missing = Vec::new();
+        for y in first.y..=last.y {
+            for x in first.x..=last.x {
+                let key = TileKey { zoom, x, y };
+                match cache.get(&key) {
+                    Some(samples) => {
+                        tiles.insert(key, samples);
+                    }
+                    None => missing.push(key),
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            let reference = self.reference_orbit();
+            let missing_pixels = missing.len() as f64 * (TILE_SIZE * TILE_SIZE) as f64;
+            let mut coarse_share = 0.;
+            if missing.len() * 2 > missing.len() + tiles.len() {
+                let coarse_pixels = (shape[0] * shape[1]) as f64 * (1. / 16. + 1. / 4.);
+                coarse_share = coarse_pixels / (coarse_pixels + missing_pixels);
+                let colorizer = self.colorizer(shape, None);
+                render_progressive(
+                    shape,
+                    &COARSE_SCALES,
+                    cancel,
+                    |points| {
+                        self.check_points(points, reference.as_ref())
+                            .par_iter()
+                            .map(|v| colorizer.colorize(v))
+                            .collect()
+                    },
+                    |event| match event {
+                        RenderEvent::Progress(fraction) => {
+                            on_event(RenderEvent::Progress(fraction * coarse_share))
+                        }
+                        event => on_event(event),
+                    },
+                )?;
+            }
+            let mut done = 0;
+            for batch in missing.chunks(TILE_BATCH) {
+                if cancel.is_cancelled() {
+                    return Err(MandelbrotError::Cancelled);
+                }
+                let computed: Vec<TileSamples> = batch
+                    .par_iter()
+                    .map(|key| {
+                        TileSamples::new(
+                            &self.check_points(&key.pixels(center), reference.as_ref()),
+                        )
+                    })
+                    .collect();
+                for (key, samples) in batch.iter().zip(computed) {
+                    tiles.insert(*key, cache.insert(*key, samples));
+                }
+                done += batch.len();
+                let fraction = done as f64 / missing.len() as f64;
+                on_event(RenderEvent::Progress(
+                    coarse_share + (1. - coarse_share) * fraction,
+                ));
+            }
+        }
+
+        let samples: Vec<Sample> = self
+            .viewport
+            .pixels(shape)
             .par_iter()
-            .map(|v| colorize(*v, &self.color_buckets))
-            .collect()
+            .map(|p| {
+                let pixel = [center[0] + p[0] as i64, center[1] + p[1] as i64];
+                let key = TileKey::containing(zoom, pixel);
+                let index =
+                    pixel[1].rem_euclid(TILE_SIZE) * TILE_SIZE + pixel[0].rem_euclid(TILE_SIZE);
+                tiles[&key].get(index as usize)
+            })
+            .collect();
+        let colorizer = self.colorizer(shape, Some(&samples));
+        let pixels: Vec<[u8; 3]> = samples.par_iter().map(|v| colorizer.colorize(v)).collect();
+        on_event(RenderEvent::Pass {
+            scale: 1,
+            pixels: &pixels,
+        });
+        Ok(pixels)
+    }
+
+    // Renders bands of rows, so only the samples of one band are held at a
+    // time. Histogram coloring needs the distribution of the whole frame,
+    // which is kept in the compact form of the tile cache until it is known.
+    pub fn create_pixel_triplets(&self, shape: [i32; 2]) -> Vec<[u8; 3]> {
+        let (left, top) = (-shape[0] / 2, -shape[1] / 2);
+        let (width, height) = (-left * 2, -top * 2);
+        let reference = self.reference_orbit();
+        let tops: Vec<i32> = (top..top + height)
+            .step_by(FRAME_BAND_ROWS as usize)
+            .collect();
+        let band_shape = |band_top: i32| [width, FRAME_BAND_ROWS.min(top + height - band_top)];
+        let mut pixels = Vec::with_capacity((width * height).max(0) as usize);
+
+        if self.color_mode == ColorMode::Histogram && self.supersampling == Supersampling::Off {
+            let bands: Vec<TileSamples> = tops
+                .iter()
+                .map(|band_top| {
+                    let samples = self.block_values(
+                        [left, *band_top],
+                        band_shape(*band_top),
+                        reference.as_ref(),
+                    );
+                    TileSamples::new(&samples)
+                })
+                .collect();
+            let mut colorizer = self.plain_colorizer();
+            let iterations = bands
+                .iter()
+                .flat_map(|band| (0..band.len()).filter_map(move |i| band.get(i).iterations));
+            colorizer.set_equalization(Equalization::from_iterations(iterations, self.depth));
+            for band in bands.iter() {
+                let samples: Vec<Sample> = (0..band.len()).map(|i| band.get(i)).collect();
+                pixels.extend(
+                    samples
+                        .par_iter()
+                        .map(|v| colorizer.colorize(v))
+                        .collect::<Vec<_>>(),
+                );
+            }
+            return pixels;
+        }
+
+        let colorizer = self.colorizer(shape, None);
+        for band_top in tops {
+            pixels.extend(self.render_block(
+                [left, band_top],
+                band_shape(band_top),
+                &colorizer,
+                reference.as_ref(),
+            ));
+        }
+        pixels
     }
 
     pub fn create_pixels(&mut self, shape: [i32; 2]) -> Vec<u8> {
-        let values = self.create_values(shape);
         let mut pixels = Vec::new();
-        for v in values.iter() {
-            let rgb_triple = colorize(*v, &self.color_buckets);
+        for rgb_triple in self.create_pixel_triplets(shape) {
             pixels.extend_from_slice(&rgb_triple);
         }
         pixels
     }
 
-    fn pixel_to_absolute(&self, point: [i32; 2]) -> [f64; 2] {
-        [
-            self.center[0] + self.step_size * point[0] as f64,
-            self.center[1] + self.step_size * point[1] as f64,
-        ]
+    pub fn create_values(&self, shape: [i32; 2]) -> Vec<Sample> {
+        let reference = self.reference_orbit();
+        match self.strategy {
+            Strategy::Subdivision if self.formula.is_quadratic() => subdivide(shape, |points| {
+                self.check_points(points, reference.as_ref())
+            }),
+            _ => self.check_points(&self.viewport.pixels(shape), reference.as_ref()),
+        }
+    }
+
+    // Colors the pixels of the block with the given top left pixel and shape,
+    // supersampled according to the current setting.
+    fn render_block(
+        &self,
+        corner: [i32; 2],
+        shape: [i32; 2],
+        colorizer: &Colorizer,
+        reference: Option<&ReferenceOrbit>,
+    ) -> Vec<[u8; 3]> {
+        if self.supersampling == Supersampling::Off {
+            return self
+                .block_values(corner, shape, reference)
+                .par_iter()
+                .map(|v| colorizer.colorize(v))
+                .collect();
+        }
+        self.color_points(&block_points(corner, shape), colorizer, reference)
+    }
+
+    // the samples of the block, row by row, with the current strategy
+    fn block_values(
+        &self,
+        corner: [i32; 2],
+        shape: [i32; 2],
+        reference: Option<&ReferenceOrbit>,
+    ) -> Vec<Sample> {
+        match self.strategy {
+            Strategy::Subdivision if self.formula.is_quadratic() => {
+                subdivide_block(corner, shape, |points| self.check_points(points, reference))
+            }
+            _ => self.check_points(&block_points(corner, shape), reference),
+        }
     }
 
-    fn create_values(&self, shape: [i32; 2]) -> Vec<Option<u32>> {
-        let mut points: Vec<[f64; 2]> = Vec::new();
-        for y in -shape[1] / 2..shape[1] / 2 {
-            for x in -shape[0] / 2..shape[0] / 2 {
-                let abs_point = self.pixel_to_absolute([x, y]);
-                points.push(abs_point);
+    // Colors the given pixels, supersampled according to the current setting.
+    fn color_points(
+        &self,
+        points: &[[i32; 2]],
+        colorizer: &Colorizer,
+        reference: Option<&ReferenceOrbit>,
+    ) -> Vec<[u8; 3]> {
+        let supersample = |pixel: [i32; 2], offsets: Vec<[f64; 2]>| {
+            let points: Vec<[f64; 2]> = offsets
+                .iter()
+                .map(|o| [pixel[0] as f64 + o[0], pixel[1] as f64 + o[1]])
+                .collect();
+            let colors: Vec<[u8; 3]> = self
+                .check_subpixels(&points, reference)
+                .iter()
+                .map(|v| colorizer.colorize(v))
+                .collect();
+            average(&colors)
+        };
+
+        match self.supersampling {
+            Supersampling::Off => self
+                .check_points(points, reference)
+                .par_iter()
+                .map(|v| colorizer.colorize(v))
+                .collect(),
+            Supersampling::Adaptive { samples, threshold } => {
+                // the centers of the four pixels around a pixel decide about its edges
+                let around = |p: &[i32; 2]| {
+                    [
+                        *p,
+                        [p[0] - 1, p[1]],
+                        [p[0] + 1, p[1]],
+                        [p[0], p[1] - 1],
+                        [p[0], p[1] + 1],
+                    ]
+                };
+                let mut index = HashMap::new();
+                let mut centers = Vec::new();
+                for p in points {
+                    for q in around(p).iter() {
+                        index.entry(*q).or_insert_with(|| {
+                            centers.push(*q);
+                            centers.len() - 1
+                        });
+                    }
+                }
+                let centers = self.check_points(&centers, reference);
+                points
+                    .par_iter()
+                    .map(|p| {
+                        let around = around(p);
+                        let center = &centers[index[&around[0]]];
+                        if around[1..]
+                            .iter()
+                            .any(|q| differs(center, &centers[index[q]], threshold))
+                        {
+                            supersample(*p, Supersampling::Jittered(samples).offsets(*p))
+                        } else {
+                            colorizer.colorize(center)
+                        }
+                    })
+                    .collect()
             }
+            pattern => points
+                .par_iter()
+                .map(|p| supersample(*p, pattern.offsets(*p)))
+                .collect(),
         }
-        let values: Vec<Option<u32>> = points
-            .par_iter()
-            .map(|p| check_mandelbrot(p, self.depth))
-            .collect();
-        values
     }
 
-    fn update_buckets(&mut self) {
-        for _ in self.color_buckets.len()..self.depth as usize {
-            let next_bucket = match self.color_buckets.last() {
-                Some(b) => b.next_bucket(self.color_loop_depth),
-                None => ColorBucket::random_bucket(),
+    // everything besides the viewport that changes the samples of a tile
+    fn tile_context(&self) -> String {
+        format!(
+            "{:?} {:?} {:?} {} {:?} {} {:?}",
+            self.fractal,
+            self.formula,
+            self.resolve_kernel(),
+            self.depth,
+            self.channels(),
+            self.shortcuts(),
+            self.orbit_trap
+        )
+    }
+
+    // Histogram coloring needs the distribution of the whole frame, which is
+    // estimated from a sparse grid when its samples are not known up front.
+    fn colorizer(&self, shape: [i32; 2], frame: Option<&[Sample]>) -> Colorizer<'_> {
+        let mut colorizer = self.plain_colorizer();
+        if self.color_mode == ColorMode::Histogram {
+            let equalization = match frame {
+                Some(samples) => Equalization::new(samples, self.depth),
+                None => {
+                    let mut points = Vec::new();
+                    for y in 0..EQUALIZATION_SIZE {
+                        for x in 0..EQUALIZATION_SIZE {
+                            points.push([
+                                x * shape[0] / EQUALIZATION_SIZE - shape[0] / 2,
+                                y * shape[1] / EQUALIZATION_SIZE - shape[1] / 2,
+                            ]);
+                        }
+                    }
+                    let samples = self.check_points(&points, self.reference_orbit().as_ref());
+                    Equalization::new(&samples, self.depth)
+                }
             };
-            self.color_buckets.push(next_bucket);
+            colorizer.set_equalization(equalization);
         }
-        debug!("ColorBuckets: {}", self.color_buckets.len());
+        colorizer
     }
-}
 
-fn colorize(value: Option<u32>, color_buckets: &[ColorBucket]) -> [u8; 3] {
-    match value {
-        Some(v) => color_buckets[v as usize].get_color(),
-        None => [0, 0, 0],
+    fn plain_colorizer(&self) -> Colorizer<'_> {
+        Colorizer::new(
+            self.color_mode,
+            self.interior_mode,
+            &self.gradient,
+            self.viewport.get_step_size(),
+            self.lighting,
+            self.averaging,
+            self.overlay,
+        )
     }
-}
 
-fn check_mandelbrot(point: &[f64; 2], max_depth: u32) -> Option<u32> {
-    let c = Complex::new(point[0], point[1]);
-    let mut z = Complex::new(0., 0.);
-    for i in 0..max_depth {
-        z = z.powf(2.) + c;
-        if z.norm() >= 2. {
-            return Some(i);
+    fn channels(&self) -> Channels {
+        Channels {
+            derivative: self.color_mode == ColorMode::Distance || self.lighting.is_some(),
+            interior: self.interior_mode != InteriorMode::Black,
+            trap: self.color_mode == ColorMode::OrbitTrap
+                || self.interior_mode == InteriorMode::OrbitTrap,
+            average: match self.color_mode {
+                ColorMode::StripeAverage => Some(Statistic::Stripe(self.averaging.density)),
+                ColorMode::TriangleAverage => Some(Statistic::TriangleInequality),
+                _ => None,
+            },
+        }
+    }
+
+    fn tracker(&self) -> Tracker<'_> {
+        Tracker::new(
+            self.formula.as_ref(),
+            self.fractal,
+            self.channels(),
+            &self.orbit_trap,
+        )
+    }
+
+    // interior coloring needs the full orbit of every non-escaping point
+    fn shortcuts(&self) -> bool {
+        self.interior_checks && self.interior_mode == InteriorMode::Black
+    }
+
+    fn resolve_kernel(&self) -> Kernel {
+        if self.formula.is_quadratic() {
+            self.kernel.resolve(self.viewport.get_step_size())
+        } else {
+            Kernel::Double
+        }
+    }
+
+    // computed once per frame, shared by all points checked with the perturbation kernel
+    fn reference_orbit(&self) -> Option<ReferenceOrbit> {
+        match self.resolve_kernel() {
+            Kernel::Perturbation => Some(ReferenceOrbit::new(
+                self.viewport.get_center(),
+                self.fractal,
+                self.viewport.get_step_size(),
+                self.depth,
+            )),
+            _ => None,
+        }
+    }
+
+    fn check_points(&self, points: &[[i32; 2]], reference: Option<&ReferenceOrbit>) -> Vec<Sample> {
+        let points: Vec<[f64; 2]> = points.iter().map(|p| [p[0] as f64, p[1] as f64]).collect();
+        self.check_subpixels(&points, reference)
+    }
+
+    fn check_subpixels(
+        &self,
+        points: &[[f64; 2]],
+        reference: Option<&ReferenceOrbit>,
+    ) -> Vec<Sample> {
+        let viewport = &self.viewport;
+        match self.resolve_kernel() {
+            Kernel::Perturbation => {
+                let reference = reference.expect("perturbation needs a reference orbit");
+                points
+                    .par_iter()
+                    .map(|p| {
+                        reference.check(viewport.subpixel_to_delta(*p), self.depth, self.tracker())
+                    })
+                    .collect()
+            }
+            Kernel::DoubleDouble => {
+                let center = viewport.get_center();
+                let center = [
+                    DoubleDouble::from_fixed(center.get_re()),
+                    DoubleDouble::from_fixed(center.get_im()),
+                ];
+                let zero = [DoubleDouble::from_f64(0.); 2];
+                points
+                    .par_iter()
+                    .map(|p| {
+                        let delta = viewport.subpixel_to_delta(*p);
+                        let point = [
+                            center[0] + DoubleDouble::from_f64(delta.re),
+                            center[1] + DoubleDouble::from_f64(delta.im),
+                        ];
+                        match self.fractal {
+                            Fractal::Mandelbrot => {
+                                check_double_double(&zero, &point, self.depth, self.tracker())
+                            }
+                            Fractal::Julia(c) => {
+                                let param =
+                                    [DoubleDouble::from_f64(c[0]), DoubleDouble::from_f64(c[1])];
+                                check_double_double(&point, &param, self.depth, self.tracker())
+                            }
+                        }
+                    })
+                    .collect()
+            }
+            _ => {
+                let shortcuts = self.shortcuts();
+                let cull =
+                    shortcuts && self.fractal == Fractal::Mandelbrot && self.formula.is_quadratic();
+                points
+                    .par_iter()
+                    .map(|p| {
+                        let point = viewport.subpixel_to_absolute(*p);
+                        let (z, c) = self.fractal.start(Complex::new(point[0], point[1]));
+                        if cull && in_main_components(c) {
+                            return Sample::default();
+                        }
+                        check_escape(
+                            self.formula.as_ref(),
+                            z,
+                            c,
+                            self.depth,
+                            shortcuts,
+                            self.tracker(),
+                        )
+                    })
+                    .collect()
+            }
         }
     }
-    None
 }
 
 impl Default for Mandelbrot {
     fn default() -> Self {
-        let mut mb = Self {
-            step_size: DEFAULT_STEP,
-            center: DEFAULT_POS,
+        Self {
+            viewport: Viewport::new(Coordinate::from_f64(DEFAULT_POS), DEFAULT_STEP),
+            fractal: Fractal::Mandelbrot,
+            formula: Box::new(Quadratic),
+            kernel: Kernel::Auto,
+            interior_checks: true,
+            strategy: Strategy::BruteForce,
+            supersampling: Supersampling::Off,
             depth: 400,
-            color_loop_depth: DEFAULT_COLOR_LOOP,
-            color_buckets: Vec::new(),
-        };
-        mb.update_buckets();
-        mb
+            color_mode: ColorMode::Banded,
+            interior_mode: InteriorMode::Black,
+            lighting: None,
+            overlay: None,
+            orbit_trap: OrbitTrap::default(),
+            averaging: Averaging::default(),
+            gradient: Gradient::default(),
+        }
+    }
+}
+
+fn block_points(corner: [i32; 2], shape: [i32; 2]) -> Vec<[i32; 2]> {
+    let mut points = Vec::new();
+    for y in corner[1]..corner[1] + shape[1] {
+        for x in corner[0]..corner[0] + shape[0] {
+            points.push([x, y]);
+        }
     }
+    points
 }
@@ -2,9 +2,18 @@ use std::error::Error;
 use std::fmt;
 use std::io;
 
+use image::ImageError;
+
 #[derive(Debug)]
 pub enum MandelbrotError {
     Io(io::Error),
+    Parse(String),
+    Cancelled,
+    // malformed palette file, with the line and what is wrong with it
+    Palette(String),
+    UnsupportedFormat(String),
+    Image(ImageError),
+    InvalidArgument(String),
 }
 
 impl From<io::Error> for MandelbrotError {
@@ -13,16 +22,34 @@ impl From<io::Error> for MandelbrotError {
     }
 }
 
+impl From<ImageError> for MandelbrotError {
+    fn from(err: ImageError) -> Self {
+        MandelbrotError::Image(err)
+    }
+}
+
 impl Error for MandelbrotError {
     fn description(&self) -> &str {
         match *self {
             MandelbrotError::Io(_) => "io",
+            MandelbrotError::Parse(_) => "parse",
+            MandelbrotError::Cancelled => "cancelled",
+            MandelbrotError::Palette(_) => "malformed palette",
+            MandelbrotError::UnsupportedFormat(_) => "unsupported format",
+            MandelbrotError::Image(_) => "image",
+            MandelbrotError::InvalidArgument(_) => "invalid argument",
         }
     }
 
     fn cause(&self) -> Option<&dyn Error> {
         match *self {
             MandelbrotError::Io(ref err) => Some(err),
+            MandelbrotError::Parse(_) => None,
+            MandelbrotError::Cancelled => None,
+            MandelbrotError::Palette(_) => None,
+            MandelbrotError::UnsupportedFormat(_) => None,
+            MandelbrotError::Image(ref err) => Some(err),
+            MandelbrotError::InvalidArgument(_) => None,
         }
     }
 }
@@ -31,6 +58,16 @@ impl fmt::Display for MandelbrotError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             MandelbrotError::Io(ref err) => write!(f, "{}/{}", self.description(), err),
+            MandelbrotError::Parse(ref text) => write!(f, "{}: {}", self.description(), text),
+            MandelbrotError::Cancelled => write!(f, "{}", self.description()),
+            MandelbrotError::Palette(ref text) => write!(f, "{}: {}", self.description(), text),
+            MandelbrotError::Image(ref err) => write!(f, "{}/{}", self.description(), err),
+            MandelbrotError::UnsupportedFormat(ref format) => {
+                write!(f, "{}: '{}'", self.description(), format)
+            }
+            MandelbrotError::InvalidArgument(ref text) => {
+                write!(f, "{}: {}", self.description(), text)
+            }
         }
     }
 }
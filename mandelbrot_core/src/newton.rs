@@ -0,0 +1,201 @@
+use num::Complex;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{snapshot, ColorBucket, Coordinate, MandelbrotError, Viewport};
+
+const DEFAULT_STEP: f64 = 1. / 200.;
+const DEFAULT_DEPTH: u32 = 100;
+const TOLERANCE: f64 = 1e-10;
+const ROOT_SEARCH_DEPTH: u32 = 1000;
+
+// coefficients in ascending order, c0 + c1 z + c2 z^2 + ...
+#[derive(Clone, Debug)]
+pub struct Polynomial {
+    coefficients: Vec<Complex<f64>>,
+}
+
+pub struct Newton {
+    viewport: Viewport,
+    polynomial: Polynomial,
+    derivative: Polynomial,
+    roots: Vec<Complex<f64>>,
+    depth: u32,
+}
+
+impl Polynomial {
+    pub fn from_coefficients(coefficients: &[[f64; 2]]) -> Polynomial {
+        let mut coefficients: Vec<Complex<f64>> = coefficients
+            .iter()
+            .map(|c| Complex::new(c[0], c[1]))
+            .collect();
+        while coefficients.len() > 1 && coefficients.last() == Some(&Complex::new(0., 0.)) {
+            coefficients.pop();
+        }
+        // no coefficients at all is the zero polynomial, of degree 0
+        if coefficients.is_empty() {
+            coefficients.push(Complex::new(0., 0.));
+        }
+        Self { coefficients }
+    }
+
+    pub fn from_roots(roots: &[[f64; 2]]) -> Polynomial {
+        let mut coefficients = vec![Complex::new(1., 0.)];
+        for root in roots {
+            let root = Complex::new(root[0], root[1]);
+            let mut next = vec![Complex::new(0., 0.); coefficients.len() + 1];
+            for (i, c) in coefficients.iter().enumerate() {
+                next[i + 1] += c;
+                next[i] -= c * root;
+            }
+            coefficients = next;
+        }
+        Self { coefficients }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    pub fn evaluate(&self, z: Complex<f64>) -> Complex<f64> {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Complex::new(0., 0.), |acc, c| acc * z + c)
+    }
+
+    pub fn derivative(&self) -> Polynomial {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| c * i as f64)
+            .collect::<Vec<_>>();
+        if coefficients.is_empty() {
+            Self {
+                coefficients: vec![Complex::new(0., 0.)],
+            }
+        } else {
+            Self { coefficients }
+        }
+    }
+
+    // Durand-Kerner iteration, finding all roots simultaneously
+    pub fn find_roots(&self) -> Vec<Complex<f64>> {
+        let degree = self.degree();
+        if degree == 0 {
+            return Vec::new();
+        }
+        let leading = self.coefficients[degree];
+        let seed = Complex::new(0.4, 0.9);
+        let mut roots: Vec<Complex<f64>> = (0..degree).map(|i| seed.powi(i as i32)).collect();
+        for _ in 0..ROOT_SEARCH_DEPTH {
+            let mut max_change: f64 = 0.;
+            for i in 0..degree {
+                let mut denominator = leading;
+                for j in 0..degree {
+                    if i != j {
+                        denominator *= roots[i] - roots[j];
+                    }
+                }
+                let change = self.evaluate(roots[i]) / denominator;
+                roots[i] -= change;
+                max_change = max_change.max(change.norm());
+            }
+            if max_change < TOLERANCE {
+                break;
+            }
+        }
+        roots
+    }
+}
+
+impl Newton {
+    pub fn new(polynomial: Polynomial) -> Newton {
+        let roots = polynomial.find_roots();
+        Self {
+            viewport: Viewport::new(Coordinate::from_f64([0., 0.]), DEFAULT_STEP),
+            derivative: polynomial.derivative(),
+            polynomial,
+            roots,
+            depth: DEFAULT_DEPTH,
+        }
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    pub fn set_depth(&mut self, value: u32) {
+        self.depth = value;
+    }
+
+    pub fn get_viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+    pub fn get_depth(&self) -> u32 {
+        self.depth
+    }
+    pub fn get_roots(&self) -> &[Complex<f64>] {
+        &self.roots
+    }
+
+    pub fn snapshot(&self, file_name: &str, shape: [i32; 2]) -> Result<(), MandelbrotError> {
+        let pixels = self.create_pixels(shape);
+        snapshot(&pixels, shape, file_name)
+    }
+
+    pub fn create_pixel_triplets(&self, shape: [i32; 2]) -> Vec<[u8; 3]> {
+        let values = self.create_values(shape);
+        values.par_iter().map(|v| self.colorize(*v)).collect()
+    }
+
+    pub fn create_pixels(&self, shape: [i32; 2]) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        for rgb_triple in self.create_pixel_triplets(shape) {
+            pixels.extend_from_slice(&rgb_triple);
+        }
+        pixels
+    }
+
+    // index of the root the pixel converges to and the iterations needed
+    pub fn create_values(&self, shape: [i32; 2]) -> Vec<Option<(usize, u32)>> {
+        let viewport = &self.viewport;
+        viewport
+            .pixels(shape)
+            .par_iter()
+            .map(|p| {
+                let point = viewport.pixel_to_absolute(*p);
+                self.check_point(Complex::new(point[0], point[1]))
+            })
+            .collect()
+    }
+
+    pub fn check_point(&self, start: Complex<f64>) -> Option<(usize, u32)> {
+        let mut z = start;
+        for i in 0..self.depth {
+            for (index, root) in self.roots.iter().enumerate() {
+                if (z - root).norm_sqr() < TOLERANCE {
+                    return Some((index, i));
+                }
+            }
+            let slope = self.derivative.evaluate(z);
+            if slope.norm_sqr() == 0. {
+                return None;
+            }
+            z -= self.polynomial.evaluate(z) / slope;
+        }
+        None
+    }
+
+    fn colorize(&self, value: Option<(usize, u32)>) -> [u8; 3] {
+        match value {
+            Some((root, iterations)) => {
+                let hue = root as f32 / self.roots.len() as f32;
+                let shade = 1. - (iterations as f32 / self.depth as f32).sqrt();
+                ColorBucket::from_hsv(hue, 0.8, shade).get_color()
+            }
+            None => [0, 0, 0],
+        }
+    }
+}
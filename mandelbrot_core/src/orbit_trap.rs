@@ -0,0 +1,122 @@
+use std::fmt;
+
+use image::RgbaImage;
+use num::Complex;
+
+use crate::MandelbrotError;
+
+// image pixels at least this opaque catch an orbit
+const OPAQUE: u8 = 128;
+
+// Shapes in the plane of z, angles in degrees. The orbit of every pixel is
+// reduced to its closest approach to the shape, or for the image trap to the
+// color of the first opaque pixel it lands on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrbitTrap {
+    Point { center: [f64; 2] },
+    Line { point: [f64; 2], angle: f64 },
+    Cross { center: [f64; 2], angle: f64 },
+    Circle { center: [f64; 2], radius: f64 },
+    Image(ImageTrap),
+}
+
+// An image spanning width units of the plane around its center, oriented like
+// the rendered pixels.
+#[derive(Clone)]
+pub struct ImageTrap {
+    path: String,
+    image: RgbaImage,
+    center: [f64; 2],
+    width: f64,
+}
+
+impl OrbitTrap {
+    pub fn distance(&self, z: Complex<f64>) -> Option<f64> {
+        match *self {
+            OrbitTrap::Point { center } => Some((z - complex(center)).norm()),
+            OrbitTrap::Line { point, angle } => Some(line_distance(z - complex(point), angle)),
+            OrbitTrap::Cross { center, angle } => {
+                let z = z - complex(center);
+                Some(line_distance(z, angle).min(line_distance(z, angle + 90.)))
+            }
+            OrbitTrap::Circle { center, radius } => {
+                Some(((z - complex(center)).norm() - radius).abs())
+            }
+            OrbitTrap::Image(_) => None,
+        }
+    }
+
+    pub fn color(&self, z: Complex<f64>) -> Option<[u8; 3]> {
+        match *self {
+            OrbitTrap::Image(ref trap) => trap.color(z),
+            _ => None,
+        }
+    }
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        OrbitTrap::Point { center: [0., 0.] }
+    }
+}
+
+impl ImageTrap {
+    pub fn open(path: &str, center: [f64; 2], width: f64) -> Result<ImageTrap, MandelbrotError> {
+        Ok(Self {
+            path: path.to_string(),
+            image: image::open(path)?.to_rgba(),
+            center,
+            width,
+        })
+    }
+
+    fn color(&self, z: Complex<f64>) -> Option<[u8; 3]> {
+        let (w, h) = self.image.dimensions();
+        let height = self.width * h as f64 / w as f64;
+        let u = (z.re - self.center[0]) / self.width + 0.5;
+        let v = (z.im - self.center[1]) / height + 0.5;
+        if !(0. ..1.).contains(&u) || !(0. ..1.).contains(&v) {
+            return None;
+        }
+        let pixel = self
+            .image
+            .get_pixel((u * w as f64) as u32, (v * h as f64) as u32)
+            .data;
+        if pixel[3] >= OPAQUE {
+            Some([pixel[0], pixel[1], pixel[2]])
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialEq for ImageTrap {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+            && self.center == other.center
+            && self.width == other.width
+            && self.image.dimensions() == other.image.dimensions()
+            && *self.image == *other.image
+    }
+}
+
+// the pixels are left out, the path tells images apart
+impl fmt::Debug for ImageTrap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ImageTrap {{ path: {:?}, center: {:?}, width: {} }}",
+            self.path, self.center, self.width
+        )
+    }
+}
+
+fn complex(point: [f64; 2]) -> Complex<f64> {
+    Complex::new(point[0], point[1])
+}
+
+// distance to the line through the origin at the given angle
+fn line_distance(z: Complex<f64>, angle: f64) -> f64 {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    (z.im * cos - z.re * sin).abs()
+}
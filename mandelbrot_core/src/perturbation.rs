@@ -0,0 +1,95 @@
+use num::Complex;
+
+use crate::fixed::precision_for_step;
+use crate::kernel::BAILOUT;
+use crate::sample::{Sample, Tracker};
+use crate::{Coordinate, Fixed, Fractal};
+
+pub struct ReferenceOrbit {
+    orbit: Vec<Complex<f64>>,
+    param: Complex<f64>,
+    julia: bool,
+}
+
+impl ReferenceOrbit {
+    pub fn new(
+        center: &Coordinate,
+        fractal: Fractal,
+        step_size: f64,
+        max_depth: u32,
+    ) -> ReferenceOrbit {
+        let bits = precision_for_step(step_size);
+        let center = center.with_bits(bits);
+        let (mut z_re, mut z_im, c_re, c_im) = match fractal {
+            Fractal::Mandelbrot => (
+                Fixed::zero(bits),
+                Fixed::zero(bits),
+                center.get_re().clone(),
+                center.get_im().clone(),
+            ),
+            Fractal::Julia(c) => (
+                center.get_re().clone(),
+                center.get_im().clone(),
+                Fixed::from_f64(c[0], bits),
+                Fixed::from_f64(c[1], bits),
+            ),
+        };
+        let param = Complex::new(c_re.to_f64(), c_im.to_f64());
+        let mut orbit = vec![Complex::new(z_re.to_f64(), z_im.to_f64())];
+        for _ in 0..max_depth {
+            let re_sqr = z_re.square();
+            let im_sqr = z_im.square();
+            let next_im = &(&z_re * &z_im).double() + &c_im;
+            z_re = &(&re_sqr - &im_sqr) + &c_re;
+            z_im = next_im;
+            let z = Complex::new(z_re.to_f64(), z_im.to_f64());
+            orbit.push(z);
+            if z.norm_sqr() >= BAILOUT {
+                break;
+            }
+        }
+        debug!(
+            "Reference orbit: {} iterations, {} bits",
+            orbit.len() - 1,
+            bits
+        );
+        Self {
+            orbit,
+            param,
+            julia: fractal != Fractal::Mandelbrot,
+        }
+    }
+
+    // Iterates the delta of a pixel to the reference, rebasing onto the start of
+    // the reference orbit whenever the delta dominates the full value (glitch)
+    // or the reference escaped before the pixel did.
+    pub fn check(&self, delta: Complex<f64>, max_depth: u32, mut tracker: Tracker) -> Sample {
+        let last = self.orbit.len() - 1;
+        let (mut dz, dc) = if self.julia {
+            (delta, Complex::new(0., 0.))
+        } else {
+            (Complex::new(0., 0.), delta)
+        };
+        let c = if self.julia {
+            self.param
+        } else {
+            self.param + delta
+        };
+        let mut ref_index = 0;
+        for i in 0..max_depth {
+            tracker.advance(self.orbit[ref_index] + dz, c);
+            dz = (self.orbit[ref_index] * 2. + dz) * dz + dc;
+            ref_index += 1;
+            let z = self.orbit[ref_index] + dz;
+            let z_norm = z.norm_sqr();
+            if z_norm >= BAILOUT {
+                return tracker.escaped(i, z);
+            }
+            if z_norm < dz.norm_sqr() || ref_index == last {
+                dz = z - self.orbit[0];
+                ref_index = 0;
+            }
+        }
+        tracker.interior(self.orbit[ref_index] + dz, Complex::new(0., 0.), c)
+    }
+}
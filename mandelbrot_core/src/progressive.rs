@@ -0,0 +1,158 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::MandelbrotError;
+
+// every pass evaluates one pixel per scale x scale block: 1/16, 1/4, all
+pub const PASS_SCALES: [i32; 3] = [4, 2, 1];
+pub const COARSE_SCALES: [i32; 2] = [4, 2];
+// rows of a pass evaluated between two progress reports
+const BAND_ROWS: usize = 16;
+
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Debug)]
+pub enum RenderEvent<'a> {
+    // fraction of all passes done, between 0 and 1
+    Progress(f64),
+    // a finished pass, upscaled to the full shape
+    Pass { scale: i32, pixels: &'a [[u8; 3]] },
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// Runs the coarse to fine passes over the pixels of Viewport::pixels, with the
+// coarse pixels evaluated at the top left pixel of their block. Pixels already
+// evaluated by the previous pass are taken over instead of evaluated again.
+pub fn render_progressive<E, F>(
+    shape: [i32; 2],
+    scales: &[i32],
+    cancel: &CancelToken,
+    evaluate: E,
+    mut on_event: F,
+) -> Result<Vec<[u8; 3]>, MandelbrotError>
+where
+    E: Fn(&[[i32; 2]]) -> Vec<[u8; 3]>,
+    F: FnMut(RenderEvent),
+{
+    let passes: Vec<(i32, Vec<i32>, Vec<i32>)> = scales
+        .iter()
+        .map(|&scale| {
+            let xs = block_starts(-shape[0] / 2..shape[0] / 2, scale);
+            let ys = block_starts(-shape[1] / 2..shape[1] / 2, scale);
+            (scale, xs, ys)
+        })
+        .collect();
+    let mut total = 0;
+    for (i, (_, xs, ys)) in passes.iter().enumerate() {
+        total += xs.len() * ys.len();
+        if i > 0 {
+            let (scale, previous_xs, previous_ys) = &passes[i - 1];
+            let reused_xs = xs
+                .iter()
+                .filter_map(|x| grid_index(previous_xs, *scale, *x));
+            let reused_ys = ys
+                .iter()
+                .filter_map(|y| grid_index(previous_ys, *scale, *y));
+            total -= reused_xs.count() * reused_ys.count();
+        }
+    }
+    let mut done = 0;
+    let mut image = Vec::new();
+    // the index of the previous pass and its evaluated pixels
+    let mut previous: Option<(usize, Vec<[u8; 3]>)> = None;
+
+    for (i, (scale, xs, ys)) in passes.iter().enumerate() {
+        let scale = *scale;
+        let reused = |x: i32, y: i32| {
+            previous.as_ref().and_then(|(i, coarse)| {
+                let (scale, xs, ys) = &passes[*i];
+                let column = grid_index(xs, *scale, x)?;
+                let row = grid_index(ys, *scale, y)?;
+                Some(coarse[row * xs.len() + column])
+            })
+        };
+        let mut coarse = Vec::with_capacity(xs.len() * ys.len());
+        for band in ys.chunks(BAND_ROWS) {
+            if cancel.is_cancelled() {
+                return Err(MandelbrotError::Cancelled);
+            }
+            let mut points = Vec::with_capacity(band.len() * xs.len());
+            for y in band {
+                for x in xs.iter() {
+                    if reused(*x, *y).is_none() {
+                        points.push([*x, *y]);
+                    }
+                }
+            }
+            let mut values = evaluate(&points).into_iter();
+            for y in band {
+                for x in xs.iter() {
+                    match reused(*x, *y) {
+                        Some(rgb) => coarse.push(rgb),
+                        None => coarse.extend(values.next()),
+                    }
+                }
+            }
+            done += points.len();
+            on_event(RenderEvent::Progress(done as f64 / total as f64));
+        }
+
+        image.clear();
+        for y in -shape[1] / 2..shape[1] / 2 {
+            let row = ((block_start(y, scale) - ys[0]) / scale) as usize * xs.len();
+            for x in -shape[0] / 2..shape[0] / 2 {
+                let column = ((block_start(x, scale) - xs[0]) / scale) as usize;
+                image.push(coarse[row + column]);
+            }
+        }
+        on_event(RenderEvent::Pass {
+            scale,
+            pixels: &image,
+        });
+        previous = Some((i, coarse));
+    }
+    Ok(image)
+}
+
+fn block_start(value: i32, scale: i32) -> i32 {
+    value - value.rem_euclid(scale)
+}
+
+fn block_starts(range: std::ops::Range<i32>, scale: i32) -> Vec<i32> {
+    let mut starts: Vec<i32> = range.map(|v| block_start(v, scale)).collect();
+    starts.dedup();
+    starts
+}
+
+// index of the value among the block starts of a pass, if it is one of them
+fn grid_index(starts: &[i32], scale: i32, value: i32) -> Option<usize> {
+    if value.rem_euclid(scale) != 0 || value < starts[0] {
+        return None;
+    }
+    let index = ((value - starts[0]) / scale) as usize;
+    if index < starts.len() {
+        Some(index)
+    } else {
+        None
+    }
+}
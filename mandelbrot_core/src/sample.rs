@@ -0,0 +1,289 @@
+use std::f64;
+
+use num::Complex;
+
+use crate::kernel::{smooth_escape, BAILOUT};
+use crate::{Formula, Fractal, OrbitTrap};
+
+const PERIOD_TOLERANCE: f64 = 1e-16;
+const MAX_PERIOD: u32 = 1024;
+const NEWTON_STEPS: u32 = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sample {
+    pub iterations: Option<f64>,
+    pub distance: Option<f64>,
+    // unit surface normal of the escape potential
+    pub normal: Option<Complex<f64>>,
+    // argument of the escaping z in turns, between 0 and 1
+    pub angle: Option<f64>,
+    // only filled for points that did not escape
+    pub magnitude: Option<f64>,
+    pub period: Option<u32>,
+    pub interior_distance: Option<f64>,
+    pub atom_domain: Option<u32>,
+    // closest approach of the orbit to the trap, or the color the image trap caught
+    pub trap_distance: Option<f64>,
+    pub trap_color: Option<[u8; 3]>,
+    // orbit statistic of the average coloring modes, between 0 and 1
+    pub average: Option<f64>,
+}
+
+// per pixel values that are only tracked when the coloring needs them
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Channels {
+    pub derivative: bool,
+    pub interior: bool,
+    pub trap: bool,
+    pub average: Option<Statistic>,
+}
+
+// Averaged over the orbit of escaping points. The stripe statistic varies
+// with the angle of z, density stripes per turn; the triangle inequality one
+// places |z_n| between the bounds of |z_n-1^d| and |c|.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Statistic {
+    Stripe(f64),
+    TriangleInequality,
+}
+
+pub struct Tracker<'a> {
+    formula: &'a dyn Formula,
+    mandelbrot: bool,
+    derivative: Option<Complex<f64>>,
+    derivative_offset: Complex<f64>,
+    interior: bool,
+    iteration: u32,
+    min_norm: f64,
+    atom_domain: u32,
+    trap: Option<&'a OrbitTrap>,
+    trap_distance: f64,
+    trap_color: Option<[u8; 3]>,
+    statistic: Option<Statistic>,
+    previous: Complex<f64>,
+    c: Complex<f64>,
+    sum: f64,
+    terms: u32,
+}
+
+impl<'a> Tracker<'a> {
+    pub fn new(
+        formula: &'a dyn Formula,
+        fractal: Fractal,
+        channels: Channels,
+        trap: &'a OrbitTrap,
+    ) -> Tracker<'a> {
+        // mandelbrot tracks dz/dc, julia tracks dz/dz0
+        let (derivative, derivative_offset) = match fractal {
+            Fractal::Mandelbrot => (Complex::new(0., 0.), Complex::new(1., 0.)),
+            Fractal::Julia(_) => (Complex::new(1., 0.), Complex::new(0., 0.)),
+        };
+        Self {
+            formula,
+            mandelbrot: fractal == Fractal::Mandelbrot,
+            derivative: if channels.derivative {
+                Some(derivative)
+            } else {
+                None
+            },
+            derivative_offset,
+            interior: channels.interior,
+            iteration: 0,
+            min_norm: f64::INFINITY,
+            atom_domain: 0,
+            trap: if channels.trap { Some(trap) } else { None },
+            trap_distance: f64::INFINITY,
+            trap_color: None,
+            statistic: channels.average,
+            previous: Complex::new(0., 0.),
+            c: Complex::new(0., 0.),
+            sum: 0.,
+            terms: 0,
+        }
+    }
+
+    // called with z_n, before the kernel computes z_n+1 = f(z_n) + c
+    pub fn advance(&mut self, z: Complex<f64>, c: Complex<f64>) {
+        if let Some(derivative) = self.derivative {
+            self.derivative = self
+                .formula
+                .derivative(z)
+                .map(|slope| slope * derivative + self.derivative_offset);
+        }
+        if self.interior {
+            // the atom domain is the n >= 1 with the smallest |z_n|
+            if self.iteration > 0 && z.norm_sqr() < self.min_norm {
+                self.min_norm = z.norm_sqr();
+                self.atom_domain = self.iteration;
+            }
+        }
+        // z_0 is the same for every mandelbrot pixel, the trap starts at z_1
+        match self.trap {
+            Some(trap) if self.iteration > 0 => {
+                if let Some(distance) = trap.distance(z) {
+                    self.trap_distance = self.trap_distance.min(distance);
+                }
+                if self.trap_color.is_none() {
+                    self.trap_color = trap.color(z);
+                }
+            }
+            _ => (),
+        }
+        if let Some(statistic) = self.statistic {
+            if self.iteration > 0 {
+                if let Some(term) = self.term(statistic, z) {
+                    self.sum += term;
+                    self.terms += 1;
+                }
+            }
+            self.previous = z;
+            self.c = c;
+        }
+        self.iteration += 1;
+    }
+
+    pub fn escaped(&self, iteration: u32, z: Complex<f64>) -> Sample {
+        let norm_sqr = z.norm_sqr();
+        let radius = norm_sqr.sqrt();
+        Sample {
+            iterations: Some(smooth_escape(iteration, norm_sqr, self.formula.degree())),
+            distance: self
+                .derivative
+                .map(|derivative| 2. * radius * radius.ln() / derivative.norm()),
+            normal: self.derivative.map(|derivative| {
+                let normal = z / derivative;
+                normal / normal.norm()
+            }),
+            angle: Some((z.arg() / (2. * f64::consts::PI)).rem_euclid(1.)),
+            average: self
+                .statistic
+                .and_then(|statistic| self.average(statistic, z)),
+            ..self.trapped()
+        }
+    }
+
+    // z and previous are the last values of the orbit, c the parameter of the pixel
+    pub fn interior(&self, z: Complex<f64>, previous: Complex<f64>, c: Complex<f64>) -> Sample {
+        if !self.interior {
+            return self.trapped();
+        }
+        let period = self.find_period(z, previous, c);
+        let interior_distance = match period {
+            Some(period) if self.mandelbrot && self.formula.is_quadratic() => {
+                interior_distance(z, c, period)
+            }
+            _ => None,
+        };
+        Sample {
+            magnitude: Some(z.norm()),
+            period,
+            interior_distance,
+            atom_domain: Some(self.atom_domain),
+            ..self.trapped()
+        }
+    }
+
+    // the trap statistics of the orbit so far
+    fn trapped(&self) -> Sample {
+        Sample {
+            trap_distance: Some(self.trap_distance).filter(|d| d.is_finite()),
+            trap_color: self.trap_color,
+            ..Sample::default()
+        }
+    }
+
+    // The averages with and without the escaping z, mixed by how far it got
+    // past the bailout: the full one just past it, the shorter one a whole
+    // iteration past it, where the neighbours escaping an iteration earlier
+    // take over. Continuous like the smooth iteration count.
+    fn average(&self, statistic: Statistic, z: Complex<f64>) -> Option<f64> {
+        let (sum, terms) = match self.term(statistic, z) {
+            Some(term) => (self.sum + term, self.terms + 1),
+            None => (self.sum, self.terms),
+        };
+        if terms == 0 {
+            return None;
+        }
+        let full = sum / terms as f64;
+        if self.terms == 0 {
+            return Some(full);
+        }
+        let shorter = self.sum / self.terms as f64;
+        let overshoot = (z.norm_sqr().ln() / BAILOUT.ln()).ln() / self.formula.degree().ln();
+        let weight = (1. - overshoot).clamp(0., 1.);
+        Some(weight * full + (1. - weight) * shorter)
+    }
+
+    // the statistic of z_n, the tracker holds z_n-1 and c
+    fn term(&self, statistic: Statistic, z: Complex<f64>) -> Option<f64> {
+        match statistic {
+            Statistic::Stripe(density) => Some(0.5 * (density * z.arg()).sin() + 0.5),
+            Statistic::TriangleInequality => {
+                let power = self.previous.norm().powf(self.formula.degree());
+                let c = self.c.norm();
+                let (low, high) = ((power - c).abs(), power + c);
+                if high - low > 0. {
+                    Some(((z.norm() - low) / (high - low)).clamp(0., 1.))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn find_period(
+        &self,
+        start: Complex<f64>,
+        previous: Complex<f64>,
+        c: Complex<f64>,
+    ) -> Option<u32> {
+        let (mut z, mut previous) = (start, previous);
+        for period in 1..=MAX_PERIOD {
+            let next = self.formula.iterate(z, previous, c);
+            previous = z;
+            z = next;
+            if (z - start).norm_sqr() < PERIOD_TOLERANCE {
+                return Some(period);
+            }
+        }
+        None
+    }
+}
+
+// Refines a point of the attracting cycle of z^2 + c with Newton's method and
+// evaluates the interior distance estimate from the derivatives along the cycle.
+fn interior_distance(start: Complex<f64>, c: Complex<f64>, period: u32) -> Option<f64> {
+    let one = Complex::new(1., 0.);
+    let zero = Complex::new(0., 0.);
+    let mut z0 = start;
+    for _ in 0..NEWTON_STEPS {
+        let (mut z, mut dz) = (z0, one);
+        for _ in 0..period {
+            dz = z * dz * 2.;
+            z = z * z + c;
+        }
+        let step = (z - z0) / (dz - one);
+        z0 -= step;
+        if step.norm_sqr() < PERIOD_TOLERANCE {
+            break;
+        }
+    }
+
+    let (mut z, mut dz, mut dc, mut dzdz, mut dcdz) = (z0, one, zero, zero, zero);
+    for _ in 0..period {
+        dcdz = (z * dcdz + dz * dc) * 2.;
+        dzdz = (dz * dz + z * dzdz) * 2.;
+        dz = z * dz * 2.;
+        dc = z * dc * 2. + one;
+        z = z * z + c;
+    }
+    if dz.norm_sqr() >= 1. {
+        return None;
+    }
+    let distance = (1. - dz.norm_sqr()) / (dcdz + dzdz * dc / (one - dz)).norm();
+    if distance.is_finite() {
+        Some(distance)
+    } else {
+        None
+    }
+}
@@ -1,14 +1,129 @@
 use std::fs::File;
+use std::io::{self, BufWriter, Write};
 
 use chrono::Local;
+use deflate::write::ZlibEncoder;
+use deflate::Compression;
 use image::png::PNGEncoder;
 use image::ColorType;
+use png::HasParameters;
 
 use crate::MandelbrotError;
 
+const IDAT_SIZE: usize = 1 << 16;
+
 pub fn snapshot(pixels: &[u8], shape: [i32; 2], path: &str) -> Result<(), MandelbrotError> {
     let file = File::create(path.to_owned() + ".png")?;
     let encoder = PNGEncoder::new(file);
     encoder.encode(pixels, shape[0] as u32, shape[1] as u32, ColorType::RGB(8))?;
     Ok(())
 }
+
+// PNG writer taking the image a few rows at a time. The rows are filtered
+// and compressed right away, and the compressed data is written out as IDAT
+// chunks, so memory does not grow with the image height.
+pub struct PngStream {
+    zlib: ZlibEncoder<IdatWriter>,
+    width: usize,
+    height: usize,
+    // rows written so far
+    rows: usize,
+    row: Vec<u8>,
+}
+
+struct IdatWriter {
+    writer: png::Writer<BufWriter<File>>,
+    buffer: Vec<u8>,
+}
+
+impl PngStream {
+    pub fn create(path: &str, shape: [i32; 2]) -> Result<PngStream, MandelbrotError> {
+        let file = File::create(path.to_owned() + ".png")?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), shape[0] as u32, shape[1] as u32);
+        encoder.set(png::ColorType::RGB).set(png::BitDepth::Eight);
+        let writer = encoder.write_header().map_err(io::Error::from)?;
+        let idat = IdatWriter {
+            writer,
+            buffer: Vec::with_capacity(IDAT_SIZE),
+        };
+        Ok(Self {
+            zlib: ZlibEncoder::new(idat, Compression::Fast),
+            width: shape[0] as usize,
+            height: shape[1] as usize,
+            rows: 0,
+            row: Vec::with_capacity(1 + 3 * shape[0] as usize),
+        })
+    }
+
+    // pixels has to hold whole rows
+    pub fn write_rows(&mut self, pixels: &[[u8; 3]]) -> Result<(), MandelbrotError> {
+        if self.width == 0 || !pixels.len().is_multiple_of(self.width) {
+            return Err(MandelbrotError::InvalidArgument(format!(
+                "{} pixels are no whole rows of {}",
+                pixels.len(),
+                self.width
+            )));
+        }
+        let rows = pixels.len() / self.width;
+        if self.rows + rows > self.height {
+            return Err(MandelbrotError::InvalidArgument(format!(
+                "{} rows written to an image of {}",
+                self.rows + rows,
+                self.height
+            )));
+        }
+        self.rows += rows;
+        for line in pixels.chunks(self.width) {
+            // "sub" filter: every byte minus the same channel of the pixel before
+            self.row.clear();
+            self.row.push(1);
+            let mut left = [0u8; 3];
+            for p in line {
+                for c in 0..3 {
+                    self.row.push(p[c].wrapping_sub(left[c]));
+                }
+                left = *p;
+            }
+            self.zlib.write_all(&self.row)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), MandelbrotError> {
+        if self.rows != self.height {
+            return Err(MandelbrotError::InvalidArgument(format!(
+                "{} rows written to an image of {}",
+                self.rows, self.height
+            )));
+        }
+        let mut idat = self.zlib.finish()?;
+        idat.flush_chunk()?;
+        // the IEND chunk is written when the png writer is dropped
+        drop(idat);
+        Ok(())
+    }
+}
+
+impl IdatWriter {
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.writer.write_chunk(png::chunk::IDAT, &self.buffer)?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+}
+
+impl Write for IdatWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= IDAT_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
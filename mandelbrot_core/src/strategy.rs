@@ -0,0 +1,162 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::Sample;
+
+// rectangles narrower than this are computed pixel by pixel
+const MIN_SIZE: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    BruteForce,
+    Subdivision,
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+impl Rect {
+    fn border(&self) -> Vec<[usize; 2]> {
+        let mut points = Vec::new();
+        for x in self.x0..=self.x1 {
+            points.push([x, self.y0]);
+            points.push([x, self.y1]);
+        }
+        for y in self.y0 + 1..self.y1 {
+            points.push([self.x0, y]);
+            points.push([self.x1, y]);
+        }
+        points
+    }
+
+    fn inside(&self) -> Vec<[usize; 2]> {
+        let mut points = Vec::new();
+        for y in self.y0 + 1..self.y1 {
+            for x in self.x0 + 1..self.x1 {
+                points.push([x, y]);
+            }
+        }
+        points
+    }
+}
+
+// The result of looking at one rectangle whose border is already known.
+struct Step {
+    points: Vec<[usize; 2]>,
+    samples: Vec<Sample>,
+    children: Vec<Rect>,
+}
+
+// Mariani-Silver: a rectangle whose whole border is interior is filled without
+// iterating its inside, which holds for the quadratic family because the
+// escape radius bounds a holomorphic function of the pixel. Only the border
+// pixels are sampled though, so an escaping filament thinner than a pixel can
+// still slip through it at high depths. Other rectangles are split along their
+// longer side, and every rectangle of one level is processed in parallel.
+// Pixels are in the same order as Viewport::pixels.
+pub fn subdivide<F>(shape: [i32; 2], check_points: F) -> Vec<Sample>
+where
+    F: Fn(&[[i32; 2]]) -> Vec<Sample> + Sync,
+{
+    subdivide_block(
+        [-shape[0] / 2, -shape[1] / 2],
+        [shape[0] / 2 * 2, shape[1] / 2 * 2],
+        check_points,
+    )
+}
+
+// The pixels of the block with the given top left pixel and shape, row by row.
+pub fn subdivide_block<F>(corner: [i32; 2], shape: [i32; 2], check_points: F) -> Vec<Sample>
+where
+    F: Fn(&[[i32; 2]]) -> Vec<Sample> + Sync,
+{
+    let width = shape[0].max(0) as usize;
+    let height = shape[1].max(0) as usize;
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let to_pixel = |p: &[usize; 2]| [p[0] as i32 + corner[0], p[1] as i32 + corner[1]];
+    let compute = |points: Vec<[usize; 2]>| {
+        let pixels: Vec<[i32; 2]> = points.iter().map(to_pixel).collect();
+        let samples = check_points(&pixels);
+        (points, samples)
+    };
+
+    let mut values: Vec<Option<Sample>> = vec![None; width * height];
+    let root = Rect {
+        x0: 0,
+        y0: 0,
+        x1: width - 1,
+        y1: height - 1,
+    };
+    let (points, samples) = compute(root.border());
+    for (p, sample) in points.iter().zip(samples) {
+        values[p[1] * width + p[0]] = Some(sample);
+    }
+
+    let mut rects = vec![root];
+    while !rects.is_empty() {
+        let steps: Vec<Step> = {
+            let values = &values;
+            rects
+                .into_par_iter()
+                .map(|rect| {
+                    let uniform = rect
+                        .border()
+                        .iter()
+                        .all(|p| values[p[1] * width + p[0]] == Some(Sample::default()));
+                    if uniform {
+                        let points = rect.inside();
+                        let samples = vec![Sample::default(); points.len()];
+                        return Step {
+                            points,
+                            samples,
+                            children: Vec::new(),
+                        };
+                    }
+                    let (w, h) = (rect.x1 - rect.x0, rect.y1 - rect.y0);
+                    if w < MIN_SIZE || h < MIN_SIZE {
+                        let (points, samples) = compute(rect.inside());
+                        return Step {
+                            points,
+                            samples,
+                            children: Vec::new(),
+                        };
+                    }
+                    let (line, children) = if w >= h {
+                        let xm = rect.x0 + w / 2;
+                        let line = (rect.y0 + 1..rect.y1).map(|y| [xm, y]).collect();
+                        let left = Rect { x1: xm, ..rect };
+                        let right = Rect { x0: xm, ..rect };
+                        (line, vec![left, right])
+                    } else {
+                        let ym = rect.y0 + h / 2;
+                        let line = (rect.x0 + 1..rect.x1).map(|x| [x, ym]).collect();
+                        let top = Rect { y1: ym, ..rect };
+                        let bottom = Rect { y0: ym, ..rect };
+                        (line, vec![top, bottom])
+                    };
+                    let (points, samples) = compute(line);
+                    Step {
+                        points,
+                        samples,
+                        children,
+                    }
+                })
+                .collect()
+        };
+
+        rects = Vec::new();
+        for step in steps {
+            for (p, sample) in step.points.iter().zip(step.samples) {
+                values[p[1] * width + p[0]] = Some(sample);
+            }
+            rects.extend(step.children);
+        }
+    }
+    values.into_iter().map(|v| v.unwrap_or_default()).collect()
+}
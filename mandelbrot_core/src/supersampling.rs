@@ -0,0 +1,83 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::Sample;
+
+// Samples per pixel are n x n for the grid and jittered patterns. The adaptive
+// mode only supersamples (jittered) pixels whose iteration count differs from
+// one of their four neighbours by more than the threshold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Supersampling {
+    Off,
+    Grid(u32),
+    Jittered(u32),
+    Adaptive { samples: u32, threshold: f64 },
+}
+
+impl Supersampling {
+    // sample positions relative to the pixel center, within -0.5..0.5
+    pub fn offsets(self, pixel: [i32; 2]) -> Vec<[f64; 2]> {
+        let n = match self {
+            Supersampling::Off => return vec![[0., 0.]],
+            Supersampling::Grid(n) | Supersampling::Jittered(n) => n.max(1),
+            Supersampling::Adaptive { samples, .. } => samples.max(1),
+        };
+        let cell = 1. / n as f64;
+        // seeded by the pixel, so a snapshot renders the same every time
+        let seed = (pixel[0] as u32 as u64) << 32 | pixel[1] as u32 as u64;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut offsets = Vec::with_capacity((n * n) as usize);
+        for y in 0..n {
+            for x in 0..n {
+                let (dx, dy) = match self {
+                    Supersampling::Grid(_) => (0.5, 0.5),
+                    _ => (rng.gen::<f64>(), rng.gen::<f64>()),
+                };
+                offsets.push([(x as f64 + dx) * cell - 0.5, (y as f64 + dy) * cell - 0.5]);
+            }
+        }
+        offsets
+    }
+}
+
+// Mean of the colors in linear light, averaging the sRGB values directly
+// would darken edges between bright and dark areas.
+pub fn average(colors: &[[u8; 3]]) -> [u8; 3] {
+    if colors.is_empty() {
+        return [0, 0, 0];
+    }
+    let mut sum = [0.; 3];
+    for color in colors {
+        for (s, c) in sum.iter_mut().zip(color.iter()) {
+            *s += to_linear(*c as f64 / 255.);
+        }
+    }
+    let mut mean = [0; 3];
+    for (m, s) in mean.iter_mut().zip(sum.iter()) {
+        *m = (from_linear(s / colors.len() as f64) * 255.).round() as u8;
+    }
+    mean
+}
+
+fn to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(value: f64) -> f64 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+pub fn differs(a: &Sample, b: &Sample, threshold: f64) -> bool {
+    match (a.iterations, b.iterations) {
+        (Some(a), Some(b)) => (a - b).abs() > threshold,
+        (a, b) => a.is_some() != b.is_some(),
+    }
+}
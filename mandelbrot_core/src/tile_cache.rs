@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::f32;
+use std::sync::Arc;
+
+use num::Complex;
+
+use crate::{Coordinate, Sample, Viewport};
+
+pub const TILE_SIZE: i64 = 64;
+// the cache holds the tiles of this many views, enough to pan back and forth
+const CACHED_VIEWS: usize = 4;
+// stands for None in the integer channels of a tile
+const NO_VALUE: u32 = u32::MAX;
+// centers closer than this (in pixels) to the tile grid share its tiles
+const GRID_TOLERANCE: f64 = 1e-3;
+
+// zoom is the bit pattern of the step size, x and y count tiles
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct TileKey {
+    pub zoom: u64,
+    pub x: i64,
+    pub y: i64,
+}
+
+// The samples of a tile, channel by channel. Channels no pixel of the tile
+// has are left empty, the others hold f32 with NaN for None, except for the
+// iterations and distances which need the precision of deep zooms.
+#[derive(Clone, Debug, Default)]
+pub struct TileSamples {
+    len: usize,
+    iterations: Vec<f64>,
+    distance: Vec<f64>,
+    // argument of the unit normal
+    normal: Vec<f32>,
+    angle: Vec<f32>,
+    magnitude: Vec<f32>,
+    period: Vec<u32>,
+    interior_distance: Vec<f64>,
+    atom_domain: Vec<u32>,
+    trap_distance: Vec<f32>,
+    trap_color: Vec<Option<[u8; 3]>>,
+    average: Vec<f32>,
+}
+
+struct Tile {
+    samples: Arc<TileSamples>,
+    last_used: u64,
+}
+
+pub struct TileCache {
+    capacity: usize,
+    context: String,
+    origins: HashMap<u64, Coordinate>,
+    tiles: HashMap<TileKey, Tile>,
+    clock: u64,
+}
+
+impl TileKey {
+    pub fn containing(zoom: u64, pixel: [i64; 2]) -> TileKey {
+        Self {
+            zoom,
+            x: pixel[0].div_euclid(TILE_SIZE),
+            y: pixel[1].div_euclid(TILE_SIZE),
+        }
+    }
+
+    // pixels of the tile in row major order, relative to the given grid index
+    pub fn pixels(&self, center: [i64; 2]) -> Vec<[i32; 2]> {
+        let mut points = Vec::with_capacity((TILE_SIZE * TILE_SIZE) as usize);
+        for y in 0..TILE_SIZE {
+            for x in 0..TILE_SIZE {
+                points.push([
+                    (self.x * TILE_SIZE + x - center[0]) as i32,
+                    (self.y * TILE_SIZE + y - center[1]) as i32,
+                ]);
+            }
+        }
+        points
+    }
+}
+
+impl TileSamples {
+    pub fn new(samples: &[Sample]) -> TileSamples {
+        Self {
+            len: samples.len(),
+            iterations: channel(samples, |s| s.iterations, |v| v, f64::NAN),
+            distance: channel(samples, |s| s.distance, |v| v, f64::NAN),
+            normal: channel(samples, |s| s.normal, |n| n.arg() as f32, f32::NAN),
+            angle: channel(samples, |s| s.angle, |v| v as f32, f32::NAN),
+            magnitude: channel(samples, |s| s.magnitude, |v| v as f32, f32::NAN),
+            period: channel(samples, |s| s.period, |v| v, NO_VALUE),
+            interior_distance: channel(samples, |s| s.interior_distance, |v| v, f64::NAN),
+            atom_domain: channel(samples, |s| s.atom_domain, |v| v, NO_VALUE),
+            trap_distance: channel(samples, |s| s.trap_distance, |v| v as f32, f32::NAN),
+            trap_color: channel(samples, |s| s.trap_color, Some, None),
+            average: channel(samples, |s| s.average, |v| v as f32, f32::NAN),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Sample {
+        let float = |values: &[f32]| values.get(index).filter(|v| !v.is_nan()).map(|v| *v as f64);
+        let double = |values: &[f64]| values.get(index).filter(|v| !v.is_nan()).copied();
+        let integer = |values: &[u32]| values.get(index).filter(|v| **v != NO_VALUE).copied();
+        Sample {
+            iterations: double(&self.iterations),
+            distance: double(&self.distance),
+            normal: float(&self.normal).map(|arg| Complex::from_polar(&1., &arg)),
+            angle: float(&self.angle),
+            magnitude: float(&self.magnitude),
+            period: integer(&self.period),
+            interior_distance: double(&self.interior_distance),
+            atom_domain: integer(&self.atom_domain),
+            trap_distance: float(&self.trap_distance),
+            trap_color: self.trap_color.get(index).copied().flatten(),
+            average: float(&self.average),
+        }
+    }
+}
+
+impl TileCache {
+    pub fn new(capacity: usize) -> TileCache {
+        Self {
+            capacity,
+            context: String::new(),
+            origins: HashMap::new(),
+            tiles: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    // room for the tiles of a few views of the given shape
+    pub fn for_shape(shape: [i32; 2]) -> TileCache {
+        Self::new(capacity_for(shape))
+    }
+
+    // the tiles over the capacity are dropped by the next insert
+    pub fn set_shape(&mut self, shape: [i32; 2]) {
+        self.capacity = capacity_for(shape);
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    // zoom levels the cache holds a grid for
+    pub fn zoom_levels(&self) -> usize {
+        self.origins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.origins.clear();
+        self.tiles.clear();
+    }
+
+    // Grid index of the viewport center. Every zoom level anchors its grid at
+    // the first center it sees, which stays valid as long as the view is only
+    // moved by whole pixels. Anything else changing the samples (the context)
+    // invalidates the whole cache.
+    pub fn anchor(&mut self, viewport: &Viewport, context: String) -> [i64; 2] {
+        if context != self.context {
+            self.clear();
+            self.context = context;
+        }
+        let zoom = viewport.get_step_size().to_bits();
+        let center = viewport.get_center();
+        if let Some(origin) = self.origins.get(&zoom) {
+            let step_size = viewport.get_step_size();
+            let offset = [
+                (center.get_re() - origin.get_re()).to_f64() / step_size,
+                (center.get_im() - origin.get_im()).to_f64() / step_size,
+            ];
+            if offset
+                .iter()
+                .all(|o| (o - o.round()).abs() < GRID_TOLERANCE)
+            {
+                return [offset[0].round() as i64, offset[1].round() as i64];
+            }
+            self.tiles.retain(|key, _| key.zoom != zoom);
+        }
+        // the origins of zoom levels without tiles left are of no use
+        let tiles = &self.tiles;
+        self.origins
+            .retain(|z, _| tiles.keys().any(|key| key.zoom == *z));
+        self.origins.insert(zoom, center.clone());
+        [0, 0]
+    }
+
+    pub fn get(&mut self, key: &TileKey) -> Option<Arc<TileSamples>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.tiles.get_mut(key).map(|tile| {
+            tile.last_used = clock;
+            tile.samples.clone()
+        })
+    }
+
+    pub fn insert(&mut self, key: TileKey, samples: TileSamples) -> Arc<TileSamples> {
+        while self.tiles.len() >= self.capacity {
+            let oldest = self
+                .tiles
+                .iter()
+                .min_by_key(|(_, tile)| tile.last_used)
+                .map(|(key, _)| *key);
+            match oldest {
+                Some(oldest) => self.tiles.remove(&oldest),
+                None => break,
+            };
+        }
+        self.clock += 1;
+        let samples = Arc::new(samples);
+        self.tiles.insert(
+            key,
+            Tile {
+                samples: samples.clone(),
+                last_used: self.clock,
+            },
+        );
+        samples
+    }
+}
+
+// a view not aligned to the grid overlaps one more tile in each direction
+fn capacity_for(shape: [i32; 2]) -> usize {
+    let tiles = |pixels: i32| (pixels.max(0) as i64 + TILE_SIZE - 1) / TILE_SIZE + 1;
+    (tiles(shape[0]) * tiles(shape[1])) as usize * CACHED_VIEWS
+}
+
+fn channel<T, U: Clone>(
+    samples: &[Sample],
+    field: impl Fn(&Sample) -> Option<T>,
+    encode: impl Fn(T) -> U,
+    none: U,
+) -> Vec<U> {
+    if samples.iter().all(|s| field(s).is_none()) {
+        return Vec::new();
+    }
+    samples
+        .iter()
+        .map(|s| field(s).map_or(none.clone(), &encode))
+        .collect()
+}
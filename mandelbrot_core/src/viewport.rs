@@ -0,0 +1,75 @@
+use num::Complex;
+
+use crate::Coordinate;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Viewport {
+    center: Coordinate,
+    center_f64: [f64; 2],
+    step_size: f64,
+}
+
+impl Viewport {
+    pub fn new(center: Coordinate, step_size: f64) -> Viewport {
+        Self {
+            center_f64: center.to_f64(),
+            center,
+            step_size,
+        }
+    }
+
+    pub fn set_center(&mut self, new_center: Coordinate) {
+        self.center_f64 = new_center.to_f64();
+        self.center = new_center;
+    }
+
+    pub fn move_center(&mut self, units: [i32; 2]) {
+        let new_center = self.center.offset(units, self.step_size);
+        self.set_center(new_center);
+    }
+
+    pub fn set_step_size(&mut self, value: f64) {
+        self.step_size = value;
+    }
+
+    pub fn zoom(&mut self, factor: f64) {
+        self.step_size *= factor;
+    }
+
+    pub fn get_center(&self) -> &Coordinate {
+        &self.center
+    }
+
+    pub fn get_step_size(&self) -> f64 {
+        self.step_size
+    }
+
+    pub fn pixel_to_absolute(&self, point: [i32; 2]) -> [f64; 2] {
+        self.subpixel_to_absolute([point[0] as f64, point[1] as f64])
+    }
+
+    pub fn pixel_to_delta(&self, point: [i32; 2]) -> Complex<f64> {
+        self.subpixel_to_delta([point[0] as f64, point[1] as f64])
+    }
+
+    pub fn subpixel_to_absolute(&self, point: [f64; 2]) -> [f64; 2] {
+        [
+            self.center_f64[0] + self.step_size * point[0],
+            self.center_f64[1] + self.step_size * point[1],
+        ]
+    }
+
+    pub fn subpixel_to_delta(&self, point: [f64; 2]) -> Complex<f64> {
+        Complex::new(self.step_size * point[0], self.step_size * point[1])
+    }
+
+    pub fn pixels(&self, shape: [i32; 2]) -> Vec<[i32; 2]> {
+        let mut points: Vec<[i32; 2]> = Vec::new();
+        for y in -shape[1] / 2..shape[1] / 2 {
+            for x in -shape[0] / 2..shape[0] / 2 {
+                points.push([x, y]);
+            }
+        }
+        points
+    }
+}
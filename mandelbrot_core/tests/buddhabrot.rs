@@ -0,0 +1,65 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::{Buddhabrot, CancelToken, Mandelbrot, MandelbrotError, RenderEvent};
+
+const SHAPE: [i32; 2] = [80, 60];
+// more than one chunk of samples, and more than one batch of chunks
+const SAMPLES: u64 = 540_000;
+
+fn counts(buddhabrot: &Buddhabrot) -> Vec<[u32; 3]> {
+    let mandelbrot = Mandelbrot::default();
+    buddhabrot.accumulate(mandelbrot.get_viewport(), mandelbrot.get_formula(), SHAPE)
+}
+
+#[test]
+fn same_seed_gives_same_counts() {
+    let mut buddhabrot = Buddhabrot::nebulabrot(SAMPLES, [100, 50, 20]);
+    buddhabrot.set_seed(7);
+    let first = counts(&buddhabrot);
+    assert!(first.iter().any(|h| h[0] > 0));
+    assert_eq!(counts(&buddhabrot), first);
+
+    buddhabrot.set_seed(8);
+    assert_ne!(counts(&buddhabrot), first);
+}
+
+#[test]
+fn batches_add_up_to_the_whole() {
+    let mandelbrot = Mandelbrot::default();
+    let buddhabrot = Buddhabrot::new(SAMPLES, 50);
+    let mut progress = Vec::new();
+    let hits = buddhabrot
+        .accumulate_cancellable(
+            mandelbrot.get_viewport(),
+            mandelbrot.get_formula(),
+            SHAPE,
+            &CancelToken::new(),
+            |event| {
+                if let RenderEvent::Progress(fraction) = event {
+                    progress.push(fraction);
+                }
+            },
+        )
+        .unwrap();
+    assert_eq!(hits, counts(&buddhabrot));
+    assert!(progress.len() > 1);
+    assert_eq!(*progress.last().unwrap(), 1.);
+}
+
+#[test]
+fn cancelled_accumulation_stops() {
+    let mandelbrot = Mandelbrot::default();
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let result = Buddhabrot::new(SAMPLES, 50).accumulate_cancellable(
+        mandelbrot.get_viewport(),
+        mandelbrot.get_formula(),
+        SHAPE,
+        &cancel,
+        |_| (),
+    );
+    match result {
+        Err(MandelbrotError::Cancelled) => {}
+        other => panic!("expected a cancelled accumulation, got {:?}", other.is_ok()),
+    }
+}
@@ -0,0 +1,59 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::{Kernel, Mandelbrot, Sample};
+
+// boundary point in the seahorse valley, found by bisection
+const DEEP_CENTER: &str = "-0.73866089888536144847843986331801,0.13401730334391565456468041004596";
+const DEEP_STEP: f64 = 1e-25;
+const SHAPE: [i32; 2] = [32, 24];
+
+fn deep_mandelbrot(kernel: Kernel) -> Mandelbrot {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_center(DEEP_CENTER.parse().unwrap());
+    mandelbrot.set_step_size(DEEP_STEP);
+    mandelbrot.set_depth(3000);
+    mandelbrot.set_kernel(kernel);
+    mandelbrot
+}
+
+fn count_distinct(values: Vec<Sample>) -> usize {
+    let mut values: Vec<Option<u64>> = values
+        .iter()
+        .map(|v| v.iterations.map(f64::to_bits))
+        .collect();
+    values.sort();
+    values.dedup();
+    values.len()
+}
+
+#[test]
+fn double_collapses_at_deep_zoom() {
+    let values = deep_mandelbrot(Kernel::Double).create_values(SHAPE);
+    assert_eq!(count_distinct(values), 1);
+}
+
+#[test]
+fn double_double_keeps_pixels_distinct() {
+    let values = deep_mandelbrot(Kernel::DoubleDouble).create_values(SHAPE);
+    assert!(count_distinct(values) >= 4);
+}
+
+#[test]
+fn double_double_matches_perturbation() {
+    let dd = deep_mandelbrot(Kernel::DoubleDouble).create_values(SHAPE);
+    let perturbation = deep_mandelbrot(Kernel::Perturbation).create_values(SHAPE);
+    for (a, b) in dd.iter().zip(perturbation.iter()) {
+        match (a.iterations, b.iterations) {
+            (Some(a), Some(b)) => assert!((a - b).abs() < 1e-6),
+            _ => assert_eq!(a, b),
+        }
+    }
+}
+
+#[test]
+fn auto_kernel_selects_by_step_size() {
+    assert_eq!(Kernel::Auto.resolve(1e-5), Kernel::Double);
+    assert_eq!(Kernel::Auto.resolve(DEEP_STEP), Kernel::DoubleDouble);
+    assert_eq!(Kernel::Auto.resolve(1e-40), Kernel::Perturbation);
+    assert_eq!(Kernel::Double.resolve(DEEP_STEP), Kernel::Double);
+}
@@ -0,0 +1,44 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::coloring::Equalization;
+use mandelbrot_core::Sample;
+
+fn escaping(iterations: f64) -> Sample {
+    Sample {
+        iterations: Some(iterations),
+        ..Sample::default()
+    }
+}
+
+#[test]
+fn deep_zoom_spans_the_whole_range() {
+    // every pixel escapes between 3000 and 3100 iterations
+    let samples: Vec<Sample> = (0..10_000)
+        .map(|i| escaping(3000. + i as f64 / 100.))
+        .collect();
+    let equalization = Equalization::new(&samples, 5000);
+    assert!(equalization.position(3000.) < 0.02);
+    assert!(equalization.position(3100.) > 0.98);
+    let mut previous = 0.;
+    for i in 0..=100 {
+        let v = 3000. + i as f64;
+        let position = equalization.position(v);
+        assert!(position >= previous, "{} goes back to {}", v, position);
+        assert!(
+            (position - i as f64 / 100.).abs() < 0.05,
+            "{} at {}",
+            v,
+            position
+        );
+        previous = position;
+    }
+}
+
+#[test]
+fn frame_without_escaping_pixels() {
+    let samples = vec![Sample::default(); 100];
+    let equalization = Equalization::new(&samples, 1000);
+    for v in [0., 1., 500., 1000., 2000.].iter() {
+        assert_eq!(equalization.position(*v), 0.);
+    }
+}
@@ -0,0 +1,115 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::fixed::precision_for_step;
+use mandelbrot_core::{Coordinate, Fixed, Viewport};
+
+const DEEP_STEP: f64 = 1e-40;
+
+fn fixed(text: &str) -> Fixed {
+    text.parse().unwrap()
+}
+
+#[test]
+fn decimals_round_trip() {
+    for text in [
+        "0",
+        "1",
+        "-2",
+        "0.9",
+        "-0.75",
+        "0.1000000000000000000000000000000000000000000000000001",
+        "-1.2345678901234567890123456789",
+        "123456789012345678901234567890.5",
+    ]
+    .iter()
+    {
+        assert_eq!(fixed(text).to_string(), *text);
+    }
+}
+
+#[test]
+fn signs_and_exponents() {
+    assert_eq!(fixed("+0.5").to_string(), "0.5");
+    assert_eq!(fixed("-0").to_string(), "0");
+    assert_eq!(fixed("1.5e3").to_string(), "1500");
+    assert_eq!(fixed("-2.5E-3").to_string(), "-0.0025");
+    assert_eq!(
+        fixed("25e-41"),
+        fixed("0.00000000000000000000000000000000000000025")
+    );
+    assert_eq!(fixed("-3e-5").to_f64(), -3e-5);
+    assert!(fixed("-3e-5").is_negative());
+    for text in ["", "-", "1.2.3", "0x10", "1e", "1e2.5", "--1"].iter() {
+        assert!(text.parse::<Fixed>().is_err(), "'{}' accepted", text);
+    }
+}
+
+#[test]
+fn coordinate_round_trip() {
+    let text = "-0.73866089888536144847843986331801,0.13401730334391565456468041004596";
+    let center: Coordinate = text.parse().unwrap();
+    assert_eq!(center.to_string(), text);
+    assert_eq!(center.to_string().parse::<Coordinate>().unwrap(), center);
+    assert_eq!(
+        "-1e-3,2.5e1".parse::<Coordinate>().unwrap().to_string(),
+        "-0.001,25"
+    );
+    assert!("0.5".parse::<Coordinate>().is_err());
+    assert!("0.5,1,2".parse::<Coordinate>().is_err());
+}
+
+#[test]
+fn parse_keeps_requested_precision() {
+    let bits = precision_for_step(DEEP_STEP);
+    let center = Coordinate::parse("-0.75,0.1", bits).unwrap();
+    assert!(center.get_bits() >= bits);
+    // 0.1 has no finite binary expansion, its rounding error has to be far
+    // below a pixel
+    let exact = fixed("0.1000000000000000000000000000000000000000000000000000000000000000");
+    let error = (center.get_im() - &exact).to_f64().abs();
+    assert!(error < DEEP_STEP * 1e-9, "0.1 is off by {}", error);
+}
+
+#[test]
+fn offset_is_exact() {
+    let center: Coordinate = "0.25,-0.5".parse().unwrap();
+    let moved = center.offset([3, -2], 0.125);
+    assert_eq!(moved.to_string(), "0.625,-0.75");
+    // offsets of powers of two are represented exactly at any depth
+    let step = 2f64.powi(-200);
+    let moved = center.offset([1, 0], step).offset([-1, 0], step);
+    assert_eq!(moved.get_re(), &center.get_re().with_bits(moved.get_bits()));
+}
+
+#[test]
+fn deep_pan_stays_on_the_typed_decimal() {
+    let bits = precision_for_step(DEEP_STEP);
+    let mut viewport = Viewport::new(Coordinate::parse("-0.75,0.1", bits).unwrap(), DEEP_STEP);
+    viewport.move_center([0, 1]);
+    let expected = fixed("0.1000000000000000000000000000000000000001");
+    let error = (viewport.get_center().get_im() - &expected).to_f64().abs();
+    // the pixel offset itself is an f64, good to about 1e-16 of a step
+    assert!(error < DEEP_STEP * 1e-9, "moved by {} too far", error);
+    assert!(viewport
+        .get_center()
+        .to_string()
+        .starts_with("-0.75,0.10000000000000000000000000000000000000009999999999"));
+}
+
+#[test]
+fn precision_for_degenerate_steps() {
+    assert_eq!(precision_for_step(0.), precision_for_step(1.));
+    assert_eq!(precision_for_step(f64::NAN), precision_for_step(1.));
+    assert_eq!(precision_for_step(f64::INFINITY), precision_for_step(1.));
+    assert!(precision_for_step(f64::MIN_POSITIVE) > 1000);
+}
+
+#[test]
+fn huge_exponents_are_rejected() {
+    for text in ["1e1000000000", "1e-1000000000", "-2.5e2147483647"].iter() {
+        assert!(text.parse::<Fixed>().is_err(), "'{}' accepted", text);
+    }
+    assert!("1e1000000000,0".parse::<Coordinate>().is_err());
+    assert_eq!(fixed("1e300").to_f64(), 1e300);
+    assert!((fixed("1e-300").to_f64() / 1e-300 - 1.).abs() < 1e-15);
+}
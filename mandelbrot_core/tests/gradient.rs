@@ -0,0 +1,69 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::{ColorStop, Gradient, Interpolation};
+
+const MODES: [Interpolation; 4] = [
+    Interpolation::Srgb,
+    Interpolation::LinearRgb,
+    Interpolation::Lab,
+    Interpolation::Lch,
+];
+
+fn black_and_white(interpolation: Interpolation) -> Gradient {
+    let stops = vec![
+        ColorStop::new(0., [0, 0, 0]),
+        ColorStop::new(0.5, [255, 255, 255]),
+    ];
+    Gradient::new(stops, interpolation)
+}
+
+fn assert_close(actual: [u8; 3], expected: [u8; 3]) {
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        assert!(
+            (*a as i32 - *e as i32).abs() <= 1,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn stops_keep_their_colors() {
+    for mode in MODES.iter() {
+        let gradient = black_and_white(*mode);
+        assert_eq!(gradient.at(0.).get_color(), [0, 0, 0], "{:?}", mode);
+        assert_eq!(gradient.at(0.5).get_color(), [255, 255, 255], "{:?}", mode);
+        assert_eq!(gradient.at(1.).get_color(), [0, 0, 0], "{:?}", mode);
+    }
+}
+
+#[test]
+fn midpoints_depend_on_the_interpolation() {
+    // halfway in sRGB, in linear light, and at half the lightness
+    let expected = [
+        (Interpolation::Srgb, 128),
+        (Interpolation::LinearRgb, 188),
+        (Interpolation::Lab, 119),
+        (Interpolation::Lch, 119),
+    ];
+    for (mode, gray) in expected.iter() {
+        let gradient = black_and_white(*mode);
+        assert_close(gradient.at(0.25).get_color(), [*gray; 3]);
+        // the last stop blends back into the first one
+        assert_close(gradient.at(0.75).get_color(), [*gray; 3]);
+    }
+}
+
+#[test]
+fn lch_keeps_the_chroma() {
+    let stops = vec![
+        ColorStop::new(0., [255, 0, 0]),
+        ColorStop::new(0.5, [0, 0, 255]),
+    ];
+    let mut gradient = Gradient::new(stops, Interpolation::Srgb);
+    assert_close(gradient.at(0.25).get_color(), [128, 0, 128]);
+    gradient.set_interpolation(Interpolation::Lch);
+    // the hue takes the short way round past magenta, at full chroma
+    assert_close(gradient.at(0.25).get_color(), [250, 0, 128]);
+}
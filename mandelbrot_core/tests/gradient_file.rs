@@ -0,0 +1,139 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::gradient_file::{
+    format_ggr, format_kfp, format_map, parse_ggr, parse_kfp, parse_map,
+};
+use mandelbrot_core::{read_gradient, write_gradient, ColorStop, MandelbrotError};
+
+const PALETTES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/palettes/");
+
+fn sample(name: &str) -> String {
+    PALETTES.to_string() + name
+}
+
+fn assert_palette_error<T>(result: Result<T, MandelbrotError>, message: &str) {
+    match result {
+        Err(MandelbrotError::Palette(text)) => assert!(
+            text.contains(message),
+            "'{}' does not contain '{}'",
+            text,
+            message
+        ),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("malformed palette accepted"),
+    }
+}
+
+#[test]
+fn map_round_trip() {
+    let gradient = read_gradient(&sample("fire.map")).unwrap();
+    let stops = gradient.get_stops();
+    assert_eq!(stops.len(), 16);
+    assert_eq!(stops[0], ColorStop::new(0., [0, 0, 0]));
+    assert_eq!(stops[8], ColorStop::new(0.5, [255, 96, 0]));
+    assert_eq!(stops[15].color, [255, 255, 255]);
+
+    let text = format_map(&gradient);
+    assert_eq!(parse_map(&text).unwrap(), gradient);
+    // the comment is dropped, the colors are written as they were read
+    let original = std::fs::read_to_string(sample("fire.map")).unwrap();
+    for (written, read) in text.lines().zip(original.lines()) {
+        assert_eq!(written, &read[..written.len()]);
+    }
+}
+
+#[test]
+fn ggr_round_trip() {
+    let gradient = read_gradient(&sample("sunset.ggr")).unwrap();
+    let stops = gradient.get_stops();
+    // the color jumps at 0.5 and 0.75, the second segment has an off-center middle
+    assert_eq!(stops.len(), 7);
+    assert_eq!(stops[0], ColorStop::new(0., [26, 15, 77]));
+    assert_eq!(stops[1], ColorStop::new(0.5, [228, 95, 54]));
+    assert_eq!(stops[2], ColorStop::new(0.5, [253, 201, 87]));
+    assert_eq!(stops[3], ColorStop::new(0.6, [254, 223, 153]));
+    assert_eq!(stops[4], ColorStop::new(0.75, [255, 245, 219]));
+    assert_eq!(stops[5], ColorStop::new(0.75, [101, 173, 218]));
+    assert_eq!(stops[6], ColorStop::new(1., [26, 15, 77]));
+
+    let text = format_ggr(&gradient);
+    assert!(text.starts_with("GIMP Gradient\n6\n"));
+    assert_eq!(parse_ggr(&text).unwrap(), gradient);
+}
+
+#[test]
+fn kfp_round_trip() {
+    let gradient = read_gradient(&sample("ocean.kfp")).unwrap();
+    assert_eq!(gradient.get_stops().len(), 5);
+    assert_eq!(gradient.get_stops()[1], ColorStop::new(0.2, [0, 64, 128]));
+    assert_eq!(gradient.get_cycle_length(), 256.);
+    assert_eq!(gradient.get_offset(), 0.125);
+    assert_eq!(gradient.color(-32.).get_color(), [0, 16, 48]);
+
+    let text = format_kfp(&gradient);
+    assert_eq!(parse_kfp(&text).unwrap(), gradient);
+}
+
+#[test]
+fn written_files_read_back() {
+    let gradient = read_gradient(&sample("sunset.ggr")).unwrap();
+    let dir = std::env::temp_dir();
+    for extension in ["map", "ggr", "kfp"].iter() {
+        let path = dir.join(format!("mandelbrot_sunset.{}", extension));
+        let path = path.to_str().unwrap();
+        write_gradient(path, &gradient).unwrap();
+        let read = read_gradient(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        // .map and .kfp only hold evenly spaced colors, so compare the colors
+        for i in 0..64 {
+            let position = i as f64 / 64.;
+            let (a, b) = (gradient.at(position), read.at(position));
+            for (a, b) in a.get_color().iter().zip(b.get_color().iter()) {
+                assert!((*a as i32 - *b as i32).abs() <= 1, "{} at {}", extension, i);
+            }
+        }
+    }
+}
+
+#[test]
+fn malformed_map() {
+    assert_palette_error(parse_map("0 0 0\n12 300 0\n"), "line 2");
+    assert_palette_error(parse_map("0 0 0\n12 34\n"), "expected 3 color components");
+    assert_palette_error(parse_map("\n\n"), "no colors");
+}
+
+#[test]
+fn malformed_ggr() {
+    assert_palette_error(parse_ggr("GIMP Palette\n1\n"), "line 1");
+    assert_palette_error(
+        parse_ggr("GIMP Gradient\nName: x\ntwo\n"),
+        "invalid segment count",
+    );
+    assert_palette_error(
+        parse_ggr("GIMP Gradient\n2\n0 0.5 1 0 0 0 1 1 1 1 1 0 0\n"),
+        "expected 2 segments, found 1",
+    );
+    assert_palette_error(
+        parse_ggr("GIMP Gradient\n1\n0 0.5 1 0 0 0 1 1 1 1 1\n"),
+        "line 3: expected 13 values",
+    );
+    assert_palette_error(
+        parse_ggr("GIMP Gradient\n1\n0.5 0.2 1 0 0 0 1 1 1 1 1 0 0\n"),
+        "out of order",
+    );
+}
+
+#[test]
+fn malformed_kfp() {
+    assert_palette_error(parse_kfp("IterDiv: 1\n"), "no Colors");
+    assert_palette_error(parse_kfp("Colors: 1,2,3,4,\n"), "expected color triples");
+    assert_palette_error(parse_kfp("Colors: 1,2,3,\nIterDiv: 0\n"), "line 2");
+}
+
+#[test]
+fn unsupported_format() {
+    match read_gradient(&sample("fire.pal")) {
+        Err(MandelbrotError::UnsupportedFormat(extension)) => assert_eq!(extension, "pal"),
+        _ => panic!("unsupported format accepted"),
+    }
+}
@@ -0,0 +1,82 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::image_palette::gradient_from_colors;
+
+const CLUSTERS: [[u8; 3]; 5] = [
+    [0, 0, 0],
+    [200, 30, 30],
+    [240, 220, 60],
+    [40, 160, 60],
+    [30, 40, 200],
+];
+
+// a few slightly different shades of every cluster color
+fn pixels() -> Vec<[u8; 3]> {
+    let mut pixels = Vec::new();
+    for shade in 0..20u8 {
+        for color in CLUSTERS.iter() {
+            pixels.push([
+                color[0].saturating_add(shade % 5),
+                color[1].saturating_add(shade % 3),
+                color[2].saturating_add(shade % 4),
+            ]);
+        }
+    }
+    pixels
+}
+
+fn stop_colors(pixels: &[[u8; 3]], count: usize) -> Vec<[u8; 3]> {
+    let gradient = gradient_from_colors(pixels, count);
+    let stops = gradient.get_stops();
+    for (i, stop) in stops.iter().enumerate() {
+        assert_eq!(stop.position, i as f64 / stops.len() as f64);
+    }
+    stops.iter().map(|s| s.color).collect()
+}
+
+fn closest_cluster(color: [u8; 3]) -> usize {
+    let distance = |c: &[u8; 3]| -> i32 {
+        c.iter()
+            .zip(color.iter())
+            .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+            .sum()
+    };
+    (0..CLUSTERS.len())
+        .min_by_key(|&i| distance(&CLUSTERS[i]))
+        .unwrap()
+}
+
+#[test]
+fn clusters_are_found_once_each() {
+    let colors = stop_colors(&pixels(), CLUSTERS.len());
+    let mut clusters: Vec<usize> = colors.iter().map(|c| closest_cluster(*c)).collect();
+    // the path starts at the darkest cluster
+    assert_eq!(clusters[0], 0);
+    clusters.sort();
+    assert_eq!(clusters, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn ordering_is_deterministic() {
+    let mut pixels = pixels();
+    let colors = stop_colors(&pixels, CLUSTERS.len());
+    assert_eq!(stop_colors(&pixels, CLUSTERS.len()), colors);
+
+    // the same clusters in another order of the pixels end up in the same order
+    pixels.reverse();
+    let reversed: Vec<usize> = stop_colors(&pixels, CLUSTERS.len())
+        .iter()
+        .map(|c| closest_cluster(*c))
+        .collect();
+    let expected: Vec<usize> = colors.iter().map(|c| closest_cluster(*c)).collect();
+    assert_eq!(reversed, expected);
+    // black, green, yellow, red, blue and back to black
+    assert_eq!(expected, vec![0, 3, 2, 1, 4]);
+}
+
+#[test]
+fn fewer_stops_than_distinct_colors() {
+    let pixels = vec![[10, 20, 30]; 50];
+    assert_eq!(stop_colors(&pixels, 4), vec![[10, 20, 30]]);
+    assert!(stop_colors(&[], 4).is_empty());
+}
@@ -0,0 +1,99 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::{ColorMode, Coordinate, InteriorMode, Mandelbrot, Sample};
+
+// nuclei of the main cardioid and the period-2 bulb, both components have a
+// distance of 1/4 from their nucleus to the boundary
+const CARDIOID: [f64; 2] = [0., 0.];
+const BULB: [f64; 2] = [-1., 0.];
+
+fn sample_at(point: [f64; 2], mode: InteriorMode) -> Sample {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_center(Coordinate::from_f64(point));
+    mandelbrot.set_interior_mode(mode);
+    // the last pixel of a 2x2 view is the center
+    mandelbrot.create_values([2, 2])[3]
+}
+
+#[test]
+fn black_skips_interior_analysis() {
+    let sample = sample_at(CARDIOID, InteriorMode::Black);
+    assert_eq!(sample, Sample::default());
+}
+
+#[test]
+fn period_of_components() {
+    for point in &[CARDIOID, [-0.2, 0.1], [0.2, -0.3]] {
+        assert_eq!(sample_at(*point, InteriorMode::Period).period, Some(1));
+    }
+    for point in &[BULB, [-1.1, 0.1], [-0.9, -0.05]] {
+        assert_eq!(sample_at(*point, InteriorMode::Period).period, Some(2));
+    }
+}
+
+#[test]
+fn atom_domain_of_nuclei() {
+    assert_eq!(
+        sample_at(CARDIOID, InteriorMode::AtomDomain).atom_domain,
+        Some(1)
+    );
+    assert_eq!(
+        sample_at(BULB, InteriorMode::AtomDomain).atom_domain,
+        Some(2)
+    );
+}
+
+#[test]
+fn magnitude_of_attracting_cycle() {
+    let cardioid = sample_at(CARDIOID, InteriorMode::Magnitude);
+    assert!(cardioid.magnitude.unwrap() < 1e-12);
+    // the period-2 cycle of the nucleus is 0 -> -1 -> 0
+    let bulb = sample_at(BULB, InteriorMode::Magnitude).magnitude.unwrap();
+    assert!(bulb < 1e-12 || (bulb - 1.).abs() < 1e-12);
+}
+
+#[test]
+fn interior_distance_bounds_true_distance() {
+    // the estimate d satisfies d / 4 <= true distance <= d
+    for point in &[CARDIOID, BULB] {
+        let distance = sample_at(*point, InteriorMode::Distance)
+            .interior_distance
+            .unwrap();
+        assert!(distance / 4. <= 0.25 && 0.25 <= distance);
+    }
+}
+
+#[test]
+fn interior_distance_shrinks_towards_boundary() {
+    let near = sample_at([0.24, 0.], InteriorMode::Distance)
+        .interior_distance
+        .unwrap();
+    let far = sample_at([0.1, 0.], InteriorMode::Distance)
+        .interior_distance
+        .unwrap();
+    assert!(near < far);
+}
+
+#[test]
+fn interior_mode_keeps_exterior_pixels() {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_center(Coordinate::from_f64([-0.75, 0.]));
+    mandelbrot.set_step_size(3. / 64.);
+    mandelbrot.set_color_mode(ColorMode::Smooth);
+    let black = mandelbrot.create_pixel_triplets([64, 48]);
+    mandelbrot.set_interior_mode(InteriorMode::Period);
+    let colored = mandelbrot.create_pixel_triplets([64, 48]);
+    let values = mandelbrot.create_values([64, 48]);
+
+    let mut interior = 0;
+    for ((sample, before), after) in values.iter().zip(black.iter()).zip(colored.iter()) {
+        if sample.iterations.is_some() {
+            assert_eq!(before, after);
+        } else if sample.period.is_some() {
+            assert_eq!(*before, [0, 0, 0]);
+            assert_ne!(*after, [0, 0, 0]);
+            interior += 1;
+        }
+    }
+    assert!(interior > 0);
+}
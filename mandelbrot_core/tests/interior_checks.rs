@@ -0,0 +1,74 @@
+extern crate mandelbrot_core;
+extern crate num;
+
+use mandelbrot_core::formula::{BurningShip, Phoenix};
+use mandelbrot_core::kernel::in_main_components;
+use mandelbrot_core::{Coordinate, Fractal, Mandelbrot};
+use num::Complex;
+
+const SHAPE: [i32; 2] = [160, 120];
+
+fn assert_same_values(mandelbrot: &mut Mandelbrot) {
+    mandelbrot.set_interior_checks(false);
+    let full = mandelbrot.create_values(SHAPE);
+    mandelbrot.set_interior_checks(true);
+    let checked = mandelbrot.create_values(SHAPE);
+
+    let escaping = full.iter().filter(|v| v.iterations.is_some()).count();
+    assert!(escaping > 0 && escaping < full.len());
+    for (a, b) in full.iter().zip(checked.iter()) {
+        assert_eq!(a, b);
+    }
+}
+
+fn view(center: [f64; 2], width: f64) -> Mandelbrot {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_center(Coordinate::from_f64(center));
+    mandelbrot.set_step_size(width / SHAPE[0] as f64);
+    mandelbrot.set_depth(1000);
+    mandelbrot
+}
+
+#[test]
+fn main_components() {
+    for c in &[[0., 0.], [-0.5, 0.5], [0.2, 0.1], [-1., 0.], [-1.2, 0.1]] {
+        assert!(in_main_components(Complex::new(c[0], c[1])));
+    }
+    for c in &[[0.3, 0.], [-0.75, 0.2], [-1.3, 0.], [0., 1.], [-0.1, 0.9]] {
+        assert!(!in_main_components(Complex::new(c[0], c[1])));
+    }
+}
+
+#[test]
+fn whole_set() {
+    assert_same_values(&mut view([-0.75, 0.], 3.));
+}
+
+#[test]
+fn seahorse_valley() {
+    assert_same_values(&mut view([-0.745, 0.11], 0.02));
+}
+
+#[test]
+fn period_three_bulb() {
+    assert_same_values(&mut view([-0.12, 0.75], 0.3));
+}
+
+#[test]
+fn julia() {
+    let mut mandelbrot = view([0., 0.], 3.);
+    mandelbrot.set_fractal(Fractal::Julia([-0.12, 0.75]));
+    assert_same_values(&mut mandelbrot);
+}
+
+#[test]
+fn other_formulas() {
+    let mut mandelbrot = view([-0.5, -0.5], 3.);
+    mandelbrot.set_formula(BurningShip);
+    assert_same_values(&mut mandelbrot);
+
+    let mut mandelbrot = view([0., 0.], 3.);
+    mandelbrot.set_formula(Phoenix::new([-0.5, 0.]));
+    mandelbrot.set_fractal(Fractal::Julia([0.5667, 0.]));
+    assert_same_values(&mut mandelbrot);
+}
@@ -0,0 +1,77 @@
+extern crate image;
+extern crate mandelbrot_core;
+
+use mandelbrot_core::lyapunov::MAX_STABILITY;
+use mandelbrot_core::{Coordinate, InteriorMode, Lyapunov, Mandelbrot, Sample};
+
+const SHAPE: [i32; 2] = [8, 6];
+
+fn lyapunov() -> Lyapunov {
+    Lyapunov::new("AB").unwrap()
+}
+
+fn snapshot(mandelbrot: &mut Mandelbrot, name: &str, center: [f64; 2]) -> Vec<[u8; 3]> {
+    mandelbrot.set_center(Coordinate::from_f64(center));
+    mandelbrot.set_step_size(1e-4);
+    let path = std::env::temp_dir().join(name);
+    let path = path.to_str().unwrap();
+    mandelbrot
+        .snapshot_lyapunov(&lyapunov(), path, SHAPE)
+        .unwrap();
+    let file = path.to_owned() + ".png";
+    let image = image::open(&file).unwrap().to_rgb();
+    std::fs::remove_file(&file).unwrap();
+    image.pixels().map(|p| p.data).collect()
+}
+
+#[test]
+fn exponent_signs() {
+    let lyapunov = lyapunov();
+    // the fixed point 1 - 1/r, where the derivative of the map is 2 - r
+    assert!((lyapunov.exponent(2.5, 2.5) - 0.5f64.ln()).abs() < 1e-6);
+    // a stable cycle of period 2
+    assert!(lyapunov.exponent(3.2, 3.2) < 0.);
+    assert!(lyapunov.exponent(3.9, 3.9) > 0.);
+    // the orbit through the critical point
+    assert_eq!(lyapunov.exponent(2., 2.), f64::NEG_INFINITY);
+    // the orbit leaves [0, 1]
+    assert!(!lyapunov.exponent(5., 5.).is_finite());
+}
+
+#[test]
+fn samples_of_stable_and_chaotic_points() {
+    let lyapunov = lyapunov();
+    let stable = lyapunov.sample(2.5, 2.5);
+    assert!((stable.iterations.unwrap() - 50. * 2f64.ln()).abs() < 1e-4);
+    assert_eq!(stable.magnitude, None);
+
+    assert_eq!(
+        lyapunov.sample(2., 2.).iterations,
+        Some(MAX_STABILITY as f64)
+    );
+
+    let chaotic = lyapunov.sample(3.9, 3.9);
+    assert_eq!(chaotic.iterations, None);
+    assert_eq!(chaotic.magnitude, Some(lyapunov.exponent(3.9, 3.9)));
+
+    assert_eq!(lyapunov.sample(5., 5.), Sample::default());
+}
+
+#[test]
+fn stable_points_are_colored_like_escape_times() {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_interior_mode(InteriorMode::Black);
+    let stable = snapshot(&mut mandelbrot, "lyapunov_stable", [2.5, 2.5]);
+    assert!(stable.iter().all(|c| *c != [0, 0, 0]));
+    let chaotic = snapshot(&mut mandelbrot, "lyapunov_chaotic", [3.9, 3.9]);
+    assert!(chaotic.iter().all(|c| *c == [0, 0, 0]));
+
+    // chaotic points are colored by the interior mode
+    mandelbrot.set_interior_mode(InteriorMode::Magnitude);
+    let chaotic = snapshot(&mut mandelbrot, "lyapunov_magnitude", [3.9, 3.9]);
+    assert!(chaotic.iter().all(|c| *c != [0, 0, 0]));
+    assert_eq!(
+        snapshot(&mut mandelbrot, "lyapunov_stable", [2.5, 2.5]),
+        stable
+    );
+}
@@ -0,0 +1,83 @@
+extern crate mandelbrot_core;
+extern crate num;
+
+use mandelbrot_core::{Newton, Polynomial};
+use num::Complex;
+
+// z^3 - 1
+fn cubic() -> Polynomial {
+    Polynomial::from_coefficients(&[[-1., 0.], [0., 0.], [0., 0.], [1., 0.]])
+}
+
+fn unity_roots() -> Vec<Complex<f64>> {
+    (0..3)
+        .map(|k| Complex::from_polar(&1., &(2. * std::f64::consts::PI * k as f64 / 3.)))
+        .collect()
+}
+
+#[test]
+fn finds_roots_of_unity() {
+    let roots = cubic().find_roots();
+    assert_eq!(roots.len(), 3);
+    for expected in unity_roots() {
+        assert!(roots.iter().any(|r| (r - expected).norm() < 1e-9));
+    }
+}
+
+#[test]
+fn from_roots_matches_coefficients() {
+    let roots: Vec<[f64; 2]> = unity_roots().iter().map(|r| [r.re, r.im]).collect();
+    let from_roots = Polynomial::from_roots(&roots);
+    let from_coefficients = cubic();
+    assert_eq!(from_roots.degree(), 3);
+    for z in &[Complex::new(0.3, -1.2), Complex::new(2., 0.5)] {
+        assert!((from_roots.evaluate(*z) - from_coefficients.evaluate(*z)).norm() < 1e-9);
+    }
+}
+
+#[test]
+fn points_near_root_converge_to_it() {
+    let newton = Newton::new(cubic());
+    for (index, root) in newton.get_roots().iter().enumerate() {
+        let start = root * 1.2 + Complex::new(0.01, -0.02);
+        match newton.check_point(start) {
+            Some((found, iterations)) => {
+                assert_eq!(found, index);
+                assert!(iterations < 10);
+            }
+            None => panic!("no convergence from {}", start),
+        }
+    }
+}
+
+#[test]
+fn basins_are_symmetric() {
+    let newton = Newton::new(cubic());
+    let values = newton.create_values([90, 90]);
+    let mut counts = [0usize; 3];
+    for (root, _) in values.iter().filter_map(|v| *v) {
+        counts[root] += 1;
+    }
+    let total: usize = counts.iter().sum();
+    assert!(total > values.len() * 9 / 10);
+    for count in counts.iter() {
+        let share = *count as f64 / total as f64;
+        assert!((share - 1. / 3.).abs() < 0.05);
+    }
+}
+
+#[test]
+fn constant_polynomials_have_no_roots() {
+    for coefficients in [&[][..], &[[0., 0.]][..], &[[2., 1.], [0., 0.]][..]].iter() {
+        let polynomial = Polynomial::from_coefficients(coefficients);
+        assert_eq!(polynomial.degree(), 0);
+        assert!(polynomial.find_roots().is_empty());
+        let newton = Newton::new(polynomial);
+        assert!(newton.get_roots().is_empty());
+        assert!(newton.create_values([4, 4]).iter().all(|v| v.is_none()));
+    }
+    assert_eq!(
+        Polynomial::from_coefficients(&[]).evaluate(Complex::new(1., 2.)),
+        Complex::new(0., 0.)
+    );
+}
@@ -0,0 +1,62 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::fixed::precision_for_step;
+use mandelbrot_core::kernel::{smooth_escape, BAILOUT};
+use mandelbrot_core::{Coordinate, Fixed, Kernel, Mandelbrot, Viewport};
+
+// misiurewicz point, pixels around it escape after a few hundred iterations
+const CENTER: &str = "0,1";
+const DEPTH: u32 = 2000;
+const SHAPE: [i32; 2] = [16, 12];
+
+// plain z^2 + c with every value held at the precision of the step
+fn fixed_escape(c: &Coordinate, bits: u32) -> Option<f64> {
+    let c = c.with_bits(bits);
+    let (mut re, mut im) = (Fixed::zero(bits), Fixed::zero(bits));
+    for i in 0..DEPTH {
+        let next_im = &(&re * &im).double() + c.get_im();
+        re = &(&re.square() - &im.square()) + c.get_re();
+        im = next_im;
+        let norm_sqr = re.to_f64() * re.to_f64() + im.to_f64() * im.to_f64();
+        if norm_sqr >= BAILOUT {
+            return Some(smooth_escape(i, norm_sqr, 2.));
+        }
+    }
+    None
+}
+
+fn assert_matches_fixed(step_size: f64) {
+    let center: Coordinate = CENTER.parse().unwrap();
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_center(center.clone());
+    mandelbrot.set_step_size(step_size);
+    mandelbrot.set_depth(DEPTH);
+    mandelbrot.set_kernel(Kernel::Perturbation);
+    let values = mandelbrot.create_values(SHAPE);
+
+    let bits = precision_for_step(step_size);
+    let pixels = Viewport::new(center.clone(), step_size).pixels(SHAPE);
+    let mut escaped = 0;
+    for (pixel, value) in pixels.iter().zip(values.iter()) {
+        let expected = fixed_escape(&center.offset(*pixel, step_size), bits);
+        match (value.iterations, expected) {
+            (Some(a), Some(b)) => {
+                assert!((a - b).abs() < 1e-6, "{:?}: {} != {}", pixel, a, b);
+                escaped += 1;
+            }
+            (a, b) => assert_eq!(a, b, "{:?}", pixel),
+        }
+    }
+    // only the pixel on the center itself stays bounded
+    assert!(escaped >= values.len() - 1);
+}
+
+#[test]
+fn perturbation_matches_fixed_at_1e_60() {
+    assert_matches_fixed(1e-60);
+}
+
+#[test]
+fn perturbation_matches_fixed_at_1e_100() {
+    assert_matches_fixed(1e-100);
+}
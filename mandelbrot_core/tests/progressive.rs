@@ -0,0 +1,76 @@
+extern crate mandelbrot_core;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use mandelbrot_core::progressive::{render_progressive, PASS_SCALES};
+use mandelbrot_core::{CancelToken, MandelbrotError, RenderEvent};
+
+fn color(point: [i32; 2]) -> [u8; 3] {
+    [point[0] as u8, point[1] as u8, 0]
+}
+
+fn assert_evaluated_once(shape: [i32; 2], scales: &[i32]) {
+    let evaluated = RefCell::new(HashMap::new());
+    let mut progress = Vec::new();
+    let image = render_progressive(
+        shape,
+        scales,
+        &CancelToken::new(),
+        |points| {
+            let mut evaluated = evaluated.borrow_mut();
+            points
+                .iter()
+                .map(|p| {
+                    *evaluated.entry(*p).or_insert(0) += 1;
+                    color(*p)
+                })
+                .collect()
+        },
+        |event| {
+            if let RenderEvent::Progress(fraction) = event {
+                progress.push(fraction);
+            }
+        },
+    )
+    .unwrap();
+
+    let mut expected = Vec::new();
+    for y in -shape[1] / 2..shape[1] / 2 {
+        for x in -shape[0] / 2..shape[0] / 2 {
+            expected.push(color([x, y]));
+        }
+    }
+    assert_eq!(image, expected);
+    for (point, count) in evaluated.borrow().iter() {
+        assert_eq!(*count, 1, "{:?} evaluated {} times", point, count);
+    }
+    assert!(progress.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(*progress.last().unwrap(), 1.);
+}
+
+#[test]
+fn passes_reuse_the_pixels_of_coarser_ones() {
+    assert_evaluated_once([64, 48], &PASS_SCALES);
+    // odd sizes put the first block start before the first pixel
+    assert_evaluated_once([37, 23], &PASS_SCALES);
+}
+
+#[test]
+fn single_pass_evaluates_every_pixel() {
+    assert_evaluated_once([37, 23], &[1]);
+}
+
+#[test]
+fn cancelled_render_stops() {
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let result = render_progressive([16, 16], &PASS_SCALES, &cancel, |_| unreachable!(), |_| ());
+    match result {
+        Err(MandelbrotError::Cancelled) => {}
+        other => panic!(
+            "expected a cancelled render, got {:?}",
+            other.map(|p| p.len())
+        ),
+    }
+}
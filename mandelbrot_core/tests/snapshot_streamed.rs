@@ -0,0 +1,82 @@
+extern crate image;
+extern crate mandelbrot_core;
+
+use mandelbrot_core::{CancelToken, Mandelbrot, MandelbrotError, PngStream, RenderEvent};
+
+const SHAPE: [i32; 2] = [64, 48];
+
+fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    path.to_str().unwrap().to_owned()
+}
+
+fn read_png(path: &str) -> Vec<u8> {
+    let file = path.to_owned() + ".png";
+    let image = image::open(&file).unwrap().to_rgb();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(image.dimensions(), (SHAPE[0] as u32, SHAPE[1] as u32));
+    image.into_raw()
+}
+
+#[test]
+fn streamed_snapshot_matches_snapshot() {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_depth(200);
+    let plain = temp_path("mandelbrot_plain");
+    mandelbrot.snapshot(&plain, SHAPE).unwrap();
+    let expected = read_png(&plain);
+
+    // 7 does not divide 48, the last band is shorter
+    for band_height in [1, 7, 48, 100].iter() {
+        let streamed = temp_path(&format!("mandelbrot_streamed_{}", band_height));
+        let mut progress = 0.;
+        mandelbrot
+            .snapshot_streamed(
+                &streamed,
+                SHAPE,
+                *band_height,
+                &CancelToken::new(),
+                |event| {
+                    if let RenderEvent::Progress(fraction) = event {
+                        progress = fraction;
+                    }
+                },
+            )
+            .unwrap();
+        assert_eq!(progress, 1.);
+        assert!(read_png(&streamed) == expected, "bands of {}", band_height);
+    }
+}
+
+#[test]
+fn band_height_has_to_be_positive() {
+    let mandelbrot = Mandelbrot::default();
+    for band_height in [0, -3].iter() {
+        let path = temp_path("mandelbrot_no_bands");
+        let result =
+            mandelbrot.snapshot_streamed(&path, SHAPE, *band_height, &CancelToken::new(), |_| ());
+        match result {
+            Err(MandelbrotError::InvalidArgument(_)) => {}
+            other => panic!("band height {} gave {:?}", band_height, other),
+        }
+    }
+}
+
+#[test]
+fn stream_checks_the_row_count() {
+    let path = temp_path("mandelbrot_rows");
+    let row = vec![[0u8; 3]; SHAPE[0] as usize];
+
+    let mut stream = PngStream::create(&path, SHAPE).unwrap();
+    stream.write_rows(&row).unwrap();
+    assert!(stream.finish().is_err());
+
+    let mut stream = PngStream::create(&path, SHAPE).unwrap();
+    assert!(stream.write_rows(&row[1..]).is_err());
+    for _ in 0..SHAPE[1] {
+        stream.write_rows(&row).unwrap();
+    }
+    assert!(stream.write_rows(&row).is_err());
+    stream.finish().unwrap();
+    assert_eq!(read_png(&path), vec![0; (SHAPE[0] * SHAPE[1] * 3) as usize]);
+}
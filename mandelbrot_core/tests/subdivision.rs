@@ -0,0 +1,75 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::formula::BurningShip;
+use mandelbrot_core::{Coordinate, Fractal, InteriorMode, Kernel, Mandelbrot, Strategy};
+
+fn view(center: [f64; 2], width: f64, shape: [i32; 2]) -> Mandelbrot {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_center(Coordinate::from_f64(center));
+    mandelbrot.set_step_size(width / shape[0] as f64);
+    mandelbrot
+}
+
+fn assert_matches_brute_force(mandelbrot: &mut Mandelbrot, shape: [i32; 2]) {
+    mandelbrot.set_strategy(Strategy::BruteForce);
+    let brute_force = mandelbrot.create_values(shape);
+    mandelbrot.set_strategy(Strategy::Subdivision);
+    let subdivided = mandelbrot.create_values(shape);
+    assert_eq!(brute_force.len(), subdivided.len());
+    for (i, (a, b)) in brute_force.iter().zip(subdivided.iter()).enumerate() {
+        assert_eq!(a, b, "pixel {}", i);
+    }
+}
+
+#[test]
+fn whole_set() {
+    let shape = [240, 180];
+    assert_matches_brute_force(&mut view([-0.75, 0.], 3., shape), shape);
+}
+
+#[test]
+fn odd_shape() {
+    let shape = [101, 77];
+    assert_matches_brute_force(&mut view([-0.75, 0.], 3., shape), shape);
+}
+
+#[test]
+fn boundary_zoom() {
+    let shape = [200, 150];
+    assert_matches_brute_force(&mut view([-0.745, 0.11], 0.02, shape), shape);
+    let mut mandelbrot = view([-0.1, 0.8], 0.4, shape);
+    mandelbrot.set_depth(1000);
+    assert_matches_brute_force(&mut mandelbrot, shape);
+}
+
+#[test]
+fn julia() {
+    let shape = [200, 150];
+    let mut mandelbrot = view([0., 0.], 3., shape);
+    mandelbrot.set_fractal(Fractal::Julia([-0.12, 0.75]));
+    assert_matches_brute_force(&mut mandelbrot, shape);
+}
+
+#[test]
+fn interior_coloring() {
+    let shape = [120, 90];
+    let mut mandelbrot = view([-0.75, 0.], 3., shape);
+    mandelbrot.set_interior_mode(InteriorMode::Period);
+    assert_matches_brute_force(&mut mandelbrot, shape);
+}
+
+#[test]
+fn perturbation() {
+    let shape = [64, 48];
+    let mut mandelbrot = view([-0.75, 0.], 3., shape);
+    mandelbrot.set_kernel(Kernel::Perturbation);
+    assert_matches_brute_force(&mut mandelbrot, shape);
+}
+
+#[test]
+fn falls_back_for_other_formulas() {
+    let shape = [120, 90];
+    let mut mandelbrot = view([-0.5, -0.5], 3., shape);
+    mandelbrot.set_formula(BurningShip);
+    assert_matches_brute_force(&mut mandelbrot, shape);
+}
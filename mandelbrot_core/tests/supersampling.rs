@@ -0,0 +1,86 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::progressive::PASS_SCALES;
+use mandelbrot_core::{CancelToken, ColorMode, Mandelbrot, Supersampling};
+
+const SHAPE: [i32; 2] = [64, 48];
+
+// one instance for all settings, the default colors are random
+fn mandelbrot() -> Mandelbrot {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_depth(100);
+    // the whole set, with bands several pixels wide away from it
+    mandelbrot.set_step_size(1. / 16.);
+    // flat bands, so only pixels on the edge of a band can change color
+    mandelbrot.set_color_mode(ColorMode::Banded);
+    mandelbrot
+}
+
+fn progressive(mandelbrot: &Mandelbrot, scales: &[i32]) -> Vec<[u8; 3]> {
+    mandelbrot
+        .render_progressive(SHAPE, scales, &CancelToken::new(), |_| ())
+        .unwrap()
+}
+
+#[test]
+fn single_sample_grid_equals_off() {
+    let mut mandelbrot = mandelbrot();
+    let off = mandelbrot.create_pixel_triplets(SHAPE);
+    let off_progressive = progressive(&mandelbrot, &[1]);
+    mandelbrot.set_supersampling(Supersampling::Grid(1));
+    assert_eq!(mandelbrot.create_pixel_triplets(SHAPE), off);
+    assert_eq!(progressive(&mandelbrot, &[1]), off_progressive);
+}
+
+#[test]
+fn grid_differs_along_the_boundary() {
+    let mut mandelbrot = mandelbrot();
+    let off = mandelbrot.create_pixel_triplets(SHAPE);
+    mandelbrot.set_supersampling(Supersampling::Grid(3));
+    let grid = mandelbrot.create_pixel_triplets(SHAPE);
+    let width = SHAPE[0] as usize;
+    let mut changed = 0;
+    for (i, (a, b)) in off.iter().zip(grid.iter()).enumerate() {
+        if a == b {
+            continue;
+        }
+        changed += 1;
+        let (x, y) = ((i % width) as i32, (i / width) as i32);
+        // the samples of a pixel reach halfway to its neighbours, which
+        // are not known at the border of the image
+        if x == 0 || y == 0 || x == SHAPE[0] - 1 || y == SHAPE[1] - 1 {
+            continue;
+        }
+        let mut neighbours = Vec::new();
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                neighbours.push(off[ny as usize * width + nx as usize]);
+            }
+        }
+        // filaments thinner than a pixel also show up inside the black set
+        let on_edge = neighbours.iter().any(|n| n != a) || *a == [0, 0, 0];
+        assert!(on_edge, "({}, {}) changed away from any edge", x, y);
+    }
+    assert!(changed > 0);
+    assert!(changed < off.len() / 2, "{} pixels changed", changed);
+}
+
+#[test]
+fn progressive_passes_are_supersampled() {
+    for supersampling in [
+        Supersampling::Grid(2),
+        Supersampling::Jittered(2),
+        Supersampling::Adaptive {
+            samples: 3,
+            threshold: 1.,
+        },
+    ]
+    .iter()
+    {
+        let mut mandelbrot = mandelbrot();
+        mandelbrot.set_supersampling(*supersampling);
+        let expected = mandelbrot.create_pixel_triplets(SHAPE);
+        assert_eq!(progressive(&mandelbrot, &PASS_SCALES), expected);
+        assert_eq!(progressive(&mandelbrot, &[1]), expected);
+    }
+}
@@ -0,0 +1,135 @@
+extern crate mandelbrot_core;
+
+use mandelbrot_core::{CancelToken, ColorMode, Mandelbrot, Sample, TileCache, TileSamples};
+use num::Complex;
+
+const SHAPE: [i32; 2] = [160, 120];
+
+#[test]
+fn tile_samples_keep_every_channel() {
+    let samples = [
+        Sample {
+            iterations: Some(12345.678901234),
+            distance: Some(3.5e-60),
+            normal: Some(Complex::from_polar(&1., &2.)),
+            angle: Some(0.25),
+            average: Some(0.5),
+            trap_distance: Some(0.125),
+            ..Sample::default()
+        },
+        Sample {
+            magnitude: Some(0.75),
+            period: Some(3),
+            interior_distance: Some(1e-70),
+            atom_domain: Some(0),
+            trap_color: Some([1, 2, 3]),
+            ..Sample::default()
+        },
+        Sample::default(),
+    ];
+    let tile = TileSamples::new(&samples);
+    // the iterations and distances are kept exactly, the others to f32
+    assert_eq!(tile.get(0).iterations, samples[0].iterations);
+    assert_eq!(tile.get(0).distance, samples[0].distance);
+    assert!((tile.get(0).normal.unwrap() - samples[0].normal.unwrap()).norm() < 1e-6);
+    assert_eq!(tile.get(0).angle, Some(0.25));
+    assert_eq!(tile.get(0).average, Some(0.5));
+    assert_eq!(tile.get(0).trap_distance, Some(0.125));
+    assert_eq!(tile.get(1), samples[1]);
+    assert_eq!(tile.get(2), Sample::default());
+}
+
+#[test]
+fn capacity_covers_several_views() {
+    let cache = TileCache::for_shape([1024, 768]);
+    // 17 x 13 tiles are touched by a view off the tile grid
+    assert!(cache.get_capacity() >= 2 * 17 * 13);
+    let mut cache = TileCache::for_shape([64, 64]);
+    let small = cache.get_capacity();
+    cache.set_shape([1920, 1080]);
+    assert!(cache.get_capacity() > small);
+}
+
+#[test]
+fn tiled_render_matches_plain_render() {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_depth(200);
+    let expected = mandelbrot.create_pixel_triplets(SHAPE);
+    let mut cache = TileCache::for_shape(SHAPE);
+    let cancel = CancelToken::new();
+    let pixels = mandelbrot
+        .render_tiled(SHAPE, &mut cache, &cancel, |_| ())
+        .unwrap();
+    assert_eq!(pixels, expected);
+    // the second render comes from the cache alone
+    let tiles = cache.len();
+    let pixels = mandelbrot
+        .render_tiled(SHAPE, &mut cache, &cancel, |_| ())
+        .unwrap();
+    assert_eq!(cache.len(), tiles);
+    assert_eq!(pixels, expected);
+}
+
+#[test]
+fn histogram_frame_matches_tiled_render() {
+    // the tiled render equalizes over the samples of the whole frame, the
+    // plain one over the compact bands of the frame
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_depth(200);
+    mandelbrot.set_color_mode(ColorMode::Histogram);
+    let pixels = mandelbrot
+        .render_tiled(
+            SHAPE,
+            &mut TileCache::for_shape(SHAPE),
+            &CancelToken::new(),
+            |_| (),
+        )
+        .unwrap();
+    assert_eq!(mandelbrot.create_pixel_triplets(SHAPE), pixels);
+}
+
+#[test]
+fn panning_only_computes_exposed_tiles() {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_depth(200);
+    let mut cache = TileCache::for_shape(SHAPE);
+    let cancel = CancelToken::new();
+    let mut render = |mandelbrot: &Mandelbrot, cache: &mut TileCache| {
+        let pixels = mandelbrot
+            .render_tiled(SHAPE, cache, &cancel, |_| ())
+            .unwrap();
+        assert_eq!(pixels, mandelbrot.create_pixel_triplets(SHAPE));
+        cache.len()
+    };
+    // columns -80..80 and rows -60..60 lie on 4 x 2 tiles
+    assert_eq!(render(&mandelbrot, &mut cache), 8);
+    // columns 20..180, tile column 2 is new while column -2 is out of view
+    mandelbrot.move_center([100, 0]);
+    assert_eq!(render(&mandelbrot, &mut cache), 10);
+    // rows -23..97, one more row of tiles below the view
+    mandelbrot.move_center([0, 37]);
+    assert_eq!(render(&mandelbrot, &mut cache), 13);
+    // back to where it started, every tile is in the cache
+    mandelbrot.move_center([-100, -37]);
+    assert_eq!(render(&mandelbrot, &mut cache), 13);
+}
+
+#[test]
+fn zoom_levels_without_tiles_are_dropped() {
+    let mut mandelbrot = Mandelbrot::default();
+    mandelbrot.set_depth(50);
+    // room for the tiles of one zoom level at a time
+    let mut cache = TileCache::new(8);
+    let cancel = CancelToken::new();
+    for _ in 0..10 {
+        mandelbrot.zoom(0.5);
+        mandelbrot
+            .render_tiled(SHAPE, &mut cache, &cancel, |_| ())
+            .unwrap();
+        assert!(
+            cache.zoom_levels() <= 2,
+            "{} zoom levels",
+            cache.zoom_levels()
+        );
+    }
+}